@@ -0,0 +1,44 @@
+//! Benchmark for `Image::convert`, run with `cargo bench --bench convert`
+use std::time::Instant;
+
+use image2::*;
+
+const WIDTH: usize = 1024;
+const HEIGHT: usize = 1024;
+const RUNS: u32 = 5;
+
+fn bench<T: Type, C: Color, U: Type, D: Color>(name: &str, image: &Image<T, C>) {
+    // Warm up
+    let _: Image<U, D> = image.convert();
+
+    let now = Instant::now();
+    for _ in 0..RUNS {
+        let _: Image<U, D> = image.convert();
+    }
+    let t = now.elapsed() / RUNS;
+    println!("{:<32} {:>8.2}ms", name, t.as_secs_f64() * 1000.0);
+}
+
+fn main() {
+    let mut image: Image<f32, Rgb> = Image::new(WIDTH, HEIGHT);
+    image.for_each(|(x, y), px| {
+        px[0] = x as f32 / WIDTH as f32;
+        px[1] = y as f32 / HEIGHT as f32;
+        px[2] = 0.5;
+    });
+
+    bench::<f32, Rgb, f32, Rgb>("f32 rgb -> rgb", &image);
+    bench::<f32, Rgb, u8, Rgb>("f32 rgb -> u8 rgb", &image);
+    bench::<f32, Rgb, f32, Gray>("f32 rgb -> gray", &image);
+    bench::<f32, Rgb, f32, Rgba>("f32 rgb -> rgba", &image);
+    bench::<f32, Rgb, f32, Xyz>("f32 rgb -> xyz", &image);
+    bench::<f32, Rgb, f32, Rec2020>("f32 rgb -> rec2020", &image);
+    bench::<f32, Rgb, f32, Lab>("f32 rgb -> lab", &image);
+    bench::<f32, Rgb, f32, Oklab>("f32 rgb -> oklab", &image);
+    bench::<f32, Rgb, f32, Hsv>("f32 rgb -> hsv", &image);
+
+    let xyz: Image<f32, Xyz> = image.convert();
+    bench::<f32, Xyz, f32, Rec2020>("f32 xyz -> rec2020", &xyz);
+    let lab: Image<f32, Lab> = image.convert();
+    bench::<f32, Lab, f32, Lch>("f32 lab -> lch", &lab);
+}
@@ -0,0 +1,283 @@
+use std::ops;
+use std::path::Path;
+
+use crate::*;
+
+/// Number of pixels stored in each word
+const BITS: usize = 64;
+
+/// A packed 1-bit image, used for masks and scanned documents
+///
+/// Each row is stored as a sequence of `u64` words with the first pixel in the least significant
+/// bit. Set bits are white (1.0) when converting to and from `Image<T, Gray>`, the file formats
+/// use the usual convention where 1 is black and are inverted when reading and writing
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bitmap {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    data: Vec<u64>,
+}
+
+impl Bitmap {
+    /// Create a new bitmap with every bit cleared
+    pub fn new(width: usize, height: usize) -> Bitmap {
+        let words_per_row = width.div_ceil(BITS);
+        Bitmap {
+            width,
+            height,
+            words_per_row,
+            data: vec![0; words_per_row * height],
+        }
+    }
+
+    /// Bitmap width
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Bitmap height
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns (width, height)
+    pub fn shape(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Packed words, `words_per_row` for each row. Bits past the end of a row are always 0
+    pub fn data(&self) -> &[u64] {
+        &self.data
+    }
+
+    /// Number of words used to store each row
+    pub fn words_per_row(&self) -> usize {
+        self.words_per_row
+    }
+
+    /// Packed words of a single row
+    pub fn row(&self, y: usize) -> &[u64] {
+        &self.data[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    pub(crate) fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.data[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    /// Get a row as bytes with the first pixel in the most significant bit, the layout used by
+    /// PBM and TIFF
+    pub(crate) fn packed_row(&self, y: usize) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self
+            .row(y)
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .map(u8::reverse_bits)
+            .collect();
+        bytes.truncate(self.width.div_ceil(8));
+        bytes
+    }
+
+    /// Set a row from bytes with the first pixel in the most significant bit
+    pub(crate) fn set_packed_row(&mut self, y: usize, bytes: &[u8]) {
+        let width = self.width;
+        let row = self.row_mut(y);
+        row.iter_mut().for_each(|x| *x = 0);
+        for (i, b) in bytes.iter().take(width.div_ceil(8)).enumerate() {
+            row[i / 8] |= (b.reverse_bits() as u64) << (8 * (i % 8));
+        }
+        self.clear_padding();
+    }
+
+    #[inline]
+    fn index(&self, x: usize, y: usize) -> (usize, u64) {
+        assert!(
+            x < self.width && y < self.height,
+            "Out of bounds: ({}, {})",
+            x,
+            y
+        );
+        (y * self.words_per_row + x / BITS, 1 << (x % BITS))
+    }
+
+    /// Get the value at (x, y)
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        let (index, bit) = self.index(x, y);
+        self.data[index] & bit != 0
+    }
+
+    /// Set the value at (x, y)
+    #[inline]
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        let (index, bit) = self.index(x, y);
+        if value {
+            self.data[index] |= bit;
+        } else {
+            self.data[index] &= !bit;
+        }
+    }
+
+    /// Set every bit to `value`
+    pub fn fill(&mut self, value: bool) {
+        let word = if value { !0 } else { 0 };
+        self.data.iter_mut().for_each(|x| *x = word);
+        self.clear_padding();
+    }
+
+    /// Number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.data.iter().map(|x| x.count_ones() as usize).sum()
+    }
+
+    /// Number of cleared bits
+    pub fn count_zeros(&self) -> usize {
+        self.width * self.height - self.count_ones()
+    }
+
+    /// Flip every bit
+    pub fn invert(&mut self) {
+        self.data.iter_mut().for_each(|x| *x = !*x);
+        self.clear_padding();
+    }
+
+    /// Clear the unused bits at the end of each row
+    fn clear_padding(&mut self) {
+        let used = self.width % BITS;
+        if used == 0 {
+            return;
+        }
+
+        let mask = (1 << used) - 1;
+        for row in self.data.chunks_exact_mut(self.words_per_row) {
+            if let Some(last) = row.last_mut() {
+                *last &= mask;
+            }
+        }
+    }
+
+    fn zip_with(&mut self, other: &Bitmap, f: impl Fn(u64, u64) -> u64) {
+        assert_eq!(self.shape(), other.shape(), "Bitmap dimensions don't match");
+        for (a, b) in self.data.iter_mut().zip(other.data.iter()) {
+            *a = f(*a, *b);
+        }
+    }
+
+    /// Create a bitmap from a grayscale image, pixels with a normalized value greater than
+    /// `threshold` are set
+    pub fn threshold<T: Type>(image: &Image<T, Gray>, threshold: f64) -> Bitmap {
+        let mut bitmap = Bitmap::new(image.width(), image.height());
+        for y in 0..image.height() {
+            let row = image.row(y);
+            let words = bitmap.row_mut(y);
+            for (x, px) in row.iter().enumerate() {
+                if px.to_norm() > threshold {
+                    words[x / BITS] |= 1 << (x % BITS);
+                }
+            }
+        }
+        bitmap
+    }
+
+    /// Convert to a grayscale image, set bits are 1.0 and cleared bits are 0.0
+    pub fn to_image<T: Type>(&self) -> Image<T, Gray> {
+        let (on, off) = (T::from_norm(1.0), T::from_norm(0.0));
+        let mut image = Image::new(self.width, self.height);
+        image.for_each(|(x, y), px| px[0] = if self.get(x, y) { on } else { off });
+        image
+    }
+
+    /// Open a PBM or TIFF file, TIFF files must be bilevel and either uncompressed or CCITT
+    /// Group 4 compressed
+    pub fn open(path: impl AsRef<Path>) -> Result<Bitmap, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .map_err(|e| Error::UnableToOpenImage(format!("{}: {}", path.display(), e)))?;
+        Bitmap::decode(&data)
+    }
+
+    /// Decode a PBM or TIFF file from memory
+    pub fn decode(data: &[u8]) -> Result<Bitmap, Error> {
+        match data.get(0..2) {
+            Some(b"P1") | Some(b"P4") => io::pbm::decode(data),
+            Some(b"II") | Some(b"MM") => io::g4::decode_tiff(data),
+            _ => Err(Error::CannotReadImage("unknown bitmap format".into())),
+        }
+    }
+
+    /// Save as binary PBM when the extension is `pbm` or as a CCITT Group 4 compressed TIFF when
+    /// the extension is `tif` or `tiff`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let ext = path
+            .extension()
+            .and_then(|x| x.to_str())
+            .map(|x| x.to_ascii_lowercase());
+        let data = match ext.as_deref() {
+            Some("pbm") => io::pbm::encode(self),
+            Some("tif") | Some("tiff") => io::g4::encode_tiff(self),
+            _ => {
+                return Err(Error::UnableToWriteImage(format!(
+                    "{}: unsupported bitmap format",
+                    path.display()
+                )))
+            }
+        };
+        std::fs::write(path, data)
+            .map_err(|e| Error::UnableToWriteImage(format!("{}: {}", path.display(), e)))
+    }
+}
+
+impl<T: Type> From<&Bitmap> for Image<T, Gray> {
+    fn from(bitmap: &Bitmap) -> Image<T, Gray> {
+        bitmap.to_image()
+    }
+}
+
+macro_rules! bit_op {
+    ($name:ident, $f:ident, $assign:ident, $fassign:ident, $op:tt) => {
+        impl<'a> ops::$assign<&'a Bitmap> for Bitmap {
+            fn $fassign(&mut self, other: &'a Bitmap) {
+                self.zip_with(other, |a, b| a $op b);
+            }
+        }
+
+        impl<'a> ops::$name<&'a Bitmap> for Bitmap {
+            type Output = Bitmap;
+
+            fn $f(mut self, other: &'a Bitmap) -> Bitmap {
+                self.zip_with(other, |a, b| a $op b);
+                self
+            }
+        }
+
+        impl<'a, 'b> ops::$name<&'b Bitmap> for &'a Bitmap {
+            type Output = Bitmap;
+
+            fn $f(self, other: &'b Bitmap) -> Bitmap {
+                self.clone().$f(other)
+            }
+        }
+    };
+}
+
+bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+bit_op!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+impl ops::Not for Bitmap {
+    type Output = Bitmap;
+
+    fn not(mut self) -> Bitmap {
+        self.invert();
+        self
+    }
+}
+
+impl ops::Not for &Bitmap {
+    type Output = Bitmap;
+
+    fn not(self) -> Bitmap {
+        !self.clone()
+    }
+}
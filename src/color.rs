@@ -2,26 +2,241 @@
 
 use crate::*;
 
-pub trait Color: Unpin + PartialEq + Eq + PartialOrd + Ord + Clone + Sync + Send {
+pub trait Color: 'static + Unpin + PartialEq + Eq + PartialOrd + Ord + Clone + Sync + Send {
     const NAME: &'static str;
     const CHANNELS: usize;
+
+    /// True when the last channel is alpha
     const ALPHA: bool = false;
 
+    /// True when the color channels are premultiplied by alpha
+    const PREMULTIPLIED: bool = false;
+
+    /// Index of the alpha channel, only used when `ALPHA` is true
+    const ALPHA_CHANNEL: usize = Self::CHANNELS.saturating_sub(1);
+
+    /// Index of each channel in the equivalent `Gray`, `GrayAlpha`, `Rgb` or `Rgba` layout used
+    /// when reading and writing files, empty when the channels are already in that order
+    const CHANNEL_ORDER: &'static [usize] = &[];
+
+    /// Transfer function of the color channels, filters such as `Kernel` and `Transform` decode
+    /// values to linear light before combining them
+    const TRANSFER: TransferFunction = TransferFunction::Linear;
+
+    /// Matrix converting the color channels, excluding alpha, to linear RGB. Set for colors that
+    /// are a linear transform of RGB so conversions between two of them use a single matrix
+    const TO_RGB_MATRIX: Option<icc::Matrix> = None;
+
+    /// Inverse of `TO_RGB_MATRIX`
+    const FROM_RGB_MATRIX: Option<icc::Matrix> = None;
+
+    /// Get straight (not premultiplied) linear RGB values
     fn to_rgb(_c: usize, _pixel: &Pixel<Self>) -> f64;
+
+    /// Convert from straight linear RGB values, the alpha channel should be set to 1.0
     fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64;
 
+    /// Decode a channel value to linear light using `TRANSFER`, alpha is left unchanged
+    fn to_linear(c: usize, x: f64) -> f64 {
+        if Self::ALPHA && c == Self::ALPHA_CHANNEL {
+            x
+        } else {
+            Self::TRANSFER.decode(x)
+        }
+    }
+
+    /// Encode a linear light channel value using `TRANSFER`, alpha is left unchanged
+    fn from_linear(c: usize, x: f64) -> f64 {
+        if Self::ALPHA && c == Self::ALPHA_CHANNEL {
+            x
+        } else {
+            Self::TRANSFER.encode(x)
+        }
+    }
+
+    /// Get the alpha value of a pixel, 1.0 if the color has no alpha channel
+    fn alpha(pixel: &Pixel<Self>) -> f64 {
+        if Self::ALPHA {
+            pixel[Self::ALPHA_CHANNEL]
+        } else {
+            1.0
+        }
+    }
+
+    /// Convert a whole pixel to straight linear RGB, colors should override this when the
+    /// channels share intermediate values
+    fn to_rgb_pixel(pixel: &Pixel<Self>) -> [f64; 3] {
+        if let Some(m) = Self::TO_RGB_MATRIX {
+            return icc::mul(&m, color_channels::<Self>(pixel));
+        }
+
+        [
+            Self::to_rgb(0, pixel),
+            Self::to_rgb(1, pixel),
+            Self::to_rgb(2, pixel),
+        ]
+    }
+
+    /// Convert straight linear RGB to a whole pixel, the alpha channel is set to 1.0
+    fn from_rgb_pixel(rgb: [f64; 3], pixel: &mut Pixel<Self>) {
+        if let Some(m) = Self::FROM_RGB_MATRIX {
+            set_color_channels(pixel, icc::mul(&m, rgb));
+            return;
+        }
+
+        let rgb: Pixel<Rgb> = Pixel::from_slice(&rgb);
+        for (c, x) in pixel.iter_mut().enumerate() {
+            *x = Self::from_rgb(c, &rgb);
+        }
+    }
+
+    /// Convert a whole pixel to another color, alpha is passed through when both colors have an
+    /// alpha channel
+    fn convert_pixel<ToColor: Color>(pixel: &Pixel<Self>, dest: &mut Pixel<ToColor>) {
+        if std::any::TypeId::of::<Self>() == std::any::TypeId::of::<ToColor>() {
+            dest.copy_from_slice(pixel.as_ref());
+            return;
+        }
+
+        ToColor::from_rgb_pixel(Self::to_rgb_pixel(pixel), dest);
+        set_alpha(Self::alpha(pixel), dest);
+    }
+
+    /// Convert a single channel to another color, alpha is passed through when both colors have
+    /// an alpha channel. Prefer `convert_pixel` when converting every channel
     fn convert<ToColor: Color>(c: usize, pixel: &Pixel<Self>) -> f64 {
-        let mut rgb: Pixel<Rgb> = Pixel::new();
-        rgb[0] = Self::to_rgb(0, pixel);
-        rgb[1] = Self::to_rgb(1, pixel);
-        rgb[2] = Self::to_rgb(2, pixel);
-        ToColor::from_rgb(c, &rgb)
+        let alpha = Self::alpha(pixel);
+        if ToColor::ALPHA && c == ToColor::ALPHA_CHANNEL {
+            return alpha;
+        }
+
+        let rgb: Pixel<Rgb> = Pixel::from_slice(&Self::to_rgb_pixel(pixel));
+        let x = ToColor::from_rgb(c, &rgb);
+
+        if ToColor::PREMULTIPLIED {
+            x * alpha
+        } else {
+            x
+        }
+    }
+}
+
+/// Index of the `i`th color channel, skipping alpha
+#[inline]
+fn color_index<C: Color>(i: usize) -> usize {
+    if C::ALPHA && i >= C::ALPHA_CHANNEL {
+        i + 1
+    } else {
+        i
+    }
+}
+
+/// Get the first three color channels of a pixel, skipping alpha
+#[inline]
+fn color_channels<C: Color>(pixel: &Pixel<C>) -> [f64; 3] {
+    [
+        pixel[color_index::<C>(0)],
+        pixel[color_index::<C>(1)],
+        pixel[color_index::<C>(2)],
+    ]
+}
+
+/// Set the first three color channels of a pixel, skipping alpha which is set to 1.0
+#[inline]
+fn set_color_channels<C: Color>(pixel: &mut Pixel<C>, values: [f64; 3]) {
+    for (i, x) in values.iter().enumerate() {
+        (*pixel)[color_index::<C>(i)] = *x;
+    }
+
+    if C::ALPHA {
+        (*pixel)[C::ALPHA_CHANNEL] = 1.0;
+    }
+}
+
+/// Set the alpha channel of a converted pixel, premultiplying the color channels if needed
+#[inline]
+fn set_alpha<C: Color>(alpha: f64, pixel: &mut Pixel<C>) {
+    if !C::ALPHA {
+        return;
+    }
+
+    (*pixel)[C::ALPHA_CHANNEL] = alpha;
+    if C::PREMULTIPLIED {
+        for (c, x) in pixel.iter_mut().enumerate() {
+            if c != C::ALPHA_CHANNEL {
+                *x *= alpha;
+            }
+        }
+    }
+}
+
+/// Multiply two matrices
+const fn mul_matrix(a: &icc::Matrix, b: &icc::Matrix) -> icc::Matrix {
+    let mut m = [[0.0; 3]; 3];
+    let mut i = 0;
+    while i < 3 {
+        let mut j = 0;
+        while j < 3 {
+            m[i][j] = a[i][0] * b[0][j] + a[i][1] * b[1][j] + a[i][2] * b[2][j];
+            j += 1;
+        }
+        i += 1;
+    }
+    m
+}
+
+/// Holds the direct conversion matrix between two colors so it's computed at compile time
+/// instead of once per pixel
+struct ConversionMatrix<C, D>(std::marker::PhantomData<(C, D)>);
+
+impl<C: Color, D: Color> ConversionMatrix<C, D> {
+    const MATRIX: Option<icc::Matrix> = match (C::TO_RGB_MATRIX, D::FROM_RGB_MATRIX) {
+        (Some(a), Some(b)) => Some(mul_matrix(&b, &a)),
+        _ => None,
+    };
+}
+
+/// Matrix converting `C` to `D` directly, when both are linear transforms of RGB
+#[inline]
+pub(crate) fn conversion_matrix<C: Color, D: Color>() -> Option<icc::Matrix> {
+    ConversionMatrix::<C, D>::MATRIX
+}
+
+/// Convert a pixel using a matrix from `conversion_matrix`
+#[inline]
+pub(crate) fn convert_pixel_matrix<C: Color, D: Color>(
+    m: &icc::Matrix,
+    pixel: &Pixel<C>,
+    dest: &mut Pixel<D>,
+) {
+    set_color_channels(dest, icc::mul(m, color_channels(pixel)));
+    set_alpha(C::alpha(pixel), dest);
+}
+
+/// Colors with straight alpha that have a premultiplied counterpart
+pub trait Premultiply: Color {
+    type Premultiplied: Color;
+}
+
+/// Colors with premultiplied alpha that have a straight counterpart
+pub trait Unpremultiply: Color {
+    type Straight: Color;
+}
+
+/// Divide by alpha, transparent pixels are black
+#[inline]
+pub(crate) fn unpremultiply(x: f64, alpha: f64) -> f64 {
+    if alpha == 0.0 {
+        0.0
+    } else {
+        x / alpha
     }
 }
 
 #[macro_export]
 macro_rules! color {
-    ($t:ident) => {
+    ($(#[$attr:meta])* $t:ident) => {
+        $(#[$attr])*
         #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
         pub struct $t;
 
@@ -30,6 +245,28 @@ macro_rules! color {
     };
 }
 
+/// Implement `to_rgb` and `from_rgb` using `to_rgb_pixel` and `from_rgb_pixel`, for colors where
+/// every channel depends on the whole pixel
+macro_rules! channels_from_pixel {
+    () => {
+        fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
+            Self::to_rgb_pixel(pixel)[c]
+        }
+
+        fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+            let mut dest = Pixel::new();
+            Self::from_rgb_pixel([pixel[0], pixel[1], pixel[2]], &mut dest);
+            dest[c]
+        }
+    };
+}
+
+/// Matrix used by colors that store linear RGB directly
+const IDENTITY: icc::Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Matrix swapping the red and blue channels
+const SWAP_RED_BLUE: icc::Matrix = [[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]];
+
 color!(Gray);
 impl Color for Gray {
     const NAME: &'static str = "gray";
@@ -40,14 +277,31 @@ impl Color for Gray {
     }
 
     fn from_rgb(_c: usize, pixel: &Pixel<Rgb>) -> f64 {
-        pixel[0] * 0.21 + pixel[1] * 0.72 + pixel[2] * 0.7
+        luma([pixel[0], pixel[1], pixel[2]])
+    }
+
+    fn from_rgb_pixel(rgb: [f64; 3], pixel: &mut Pixel<Self>) {
+        pixel.copy_from_slice(&[luma(rgb)]);
     }
 }
 
-color!(Rgb);
+/// Luminance of linear RGB
+#[inline]
+fn luma(rgb: [f64; 3]) -> f64 {
+    rgb[0] * 0.2126 + rgb[1] * 0.7152 + rgb[2] * 0.0722
+}
+
+color!(
+    /// RGB as stored, no transfer function is applied when reading or writing files. Conversions
+    /// to other colors treat the values as linear light, use `Srgb` or `LinearRgb` when the
+    /// encoding of the data is known
+    Rgb
+);
 impl Color for Rgb {
     const NAME: &'static str = "rgb";
     const CHANNELS: usize = 3;
+    const TO_RGB_MATRIX: Option<icc::Matrix> = Some(IDENTITY);
+    const FROM_RGB_MATRIX: Option<icc::Matrix> = Some(IDENTITY);
 
     fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
         pixel[c]
@@ -58,13 +312,143 @@ impl Color for Rgb {
     }
 }
 
+color!(
+    /// RGB encoded with the sRGB transfer function, this is how most 8-bit images are stored.
+    /// Linear light files such as EXR are encoded when read and decoded when written
+    Srgb
+);
+impl Color for Srgb {
+    const NAME: &'static str = "srgb";
+    const CHANNELS: usize = 3;
+    const TRANSFER: TransferFunction = TransferFunction::Srgb;
+
+    fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
+        Self::to_linear(c, pixel[c])
+    }
+
+    fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+        Self::from_linear(c, pixel[c])
+    }
+
+    fn from_rgb_pixel(rgb: [f64; 3], pixel: &mut Pixel<Self>) {
+        for (c, x) in rgb.iter().enumerate() {
+            (*pixel)[c] = Self::from_linear(c, *x);
+        }
+    }
+}
+
+color!(
+    /// RGB in linear light, the same as `Rgb` but explicit about the transfer function: files that
+    /// aren't linear light, such as PNG or JPEG images, are decoded from sRGB when read and encoded
+    /// when written
+    LinearRgb
+);
+impl Color for LinearRgb {
+    const NAME: &'static str = "linear_rgb";
+    const CHANNELS: usize = 3;
+    const TO_RGB_MATRIX: Option<icc::Matrix> = Some(IDENTITY);
+    const FROM_RGB_MATRIX: Option<icc::Matrix> = Some(IDENTITY);
+
+    fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
+        pixel[c]
+    }
+
+    fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+        pixel[c]
+    }
+}
+
+/// Linear BT.709/sRGB primaries to linear BT.2020 primaries, from ITU-R BT.2087
+const RGB_TO_REC2020: icc::Matrix = [
+    [0.627403895934699, 0.329283038377884, 0.043313065687417],
+    [0.069097289358232, 0.919540395075459, 0.011362315566309],
+    [0.016391438875150, 0.088013307877226, 0.895595253247624],
+];
+
+/// Inverse of `RGB_TO_REC2020`
+const REC2020_TO_RGB: icc::Matrix = [
+    [1.660491002108435, -0.587641138788550, -0.072849863319884],
+    [-0.124550474521591, 1.13289989712596, -0.008349422604369],
+    [-0.018150763354905, -0.100578898008007, 1.118729661362913],
+];
+
+color!(
+    /// Linear light RGB with ITU-R BT.2020 primaries, used for HDR and wide gamut video
+    Rec2020
+);
+impl Color for Rec2020 {
+    const NAME: &'static str = "rec2020";
+    const CHANNELS: usize = 3;
+    const TO_RGB_MATRIX: Option<icc::Matrix> = Some(REC2020_TO_RGB);
+    const FROM_RGB_MATRIX: Option<icc::Matrix> = Some(RGB_TO_REC2020);
+
+    fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
+        icc::mul(&REC2020_TO_RGB, [pixel[0], pixel[1], pixel[2]])[c]
+    }
+
+    fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+        icc::mul(&RGB_TO_REC2020, [pixel[0], pixel[1], pixel[2]])[c]
+    }
+}
+
+color!(
+    /// BT.2100 PQ: BT.2020 primaries encoded with the SMPTE ST 2084 curve used by HDR10. Linear
+    /// 1.0 is 10000 cd/m², use `filter::Encode` to write `Rec2020` data with a different peak
+    Rec2100Pq
+);
+impl Color for Rec2100Pq {
+    const NAME: &'static str = "rec2100_pq";
+    const CHANNELS: usize = 3;
+    const TRANSFER: TransferFunction = TransferFunction::Pq(transfer::PQ_MAX_LUMINANCE);
+
+    channels_from_pixel!();
+
+    fn to_rgb_pixel(pixel: &Pixel<Self>) -> [f64; 3] {
+        let linear = [0, 1, 2].map(|c| Self::to_linear(c, pixel[c]));
+        icc::mul(&REC2020_TO_RGB, linear)
+    }
+
+    fn from_rgb_pixel(rgb: [f64; 3], pixel: &mut Pixel<Self>) {
+        for (c, x) in icc::mul(&RGB_TO_REC2020, rgb).iter().enumerate() {
+            (*pixel)[c] = Self::from_linear(c, *x);
+        }
+    }
+}
+
+color!(
+    /// BT.2100 HLG: BT.2020 primaries encoded with the hybrid log-gamma OETF, linear values are
+    /// scene light in `0..=1`
+    Rec2100Hlg
+);
+impl Color for Rec2100Hlg {
+    const NAME: &'static str = "rec2100_hlg";
+    const CHANNELS: usize = 3;
+    const TRANSFER: TransferFunction = TransferFunction::Hlg;
+
+    channels_from_pixel!();
+
+    fn to_rgb_pixel(pixel: &Pixel<Self>) -> [f64; 3] {
+        let linear = [0, 1, 2].map(|c| Self::to_linear(c, pixel[c]));
+        icc::mul(&REC2020_TO_RGB, linear)
+    }
+
+    fn from_rgb_pixel(rgb: [f64; 3], pixel: &mut Pixel<Self>) {
+        for (c, x) in icc::mul(&RGB_TO_REC2020, rgb).iter().enumerate() {
+            (*pixel)[c] = Self::from_linear(c, *x);
+        }
+    }
+}
+
 color!(Rgba);
 impl Color for Rgba {
     const NAME: &'static str = "rgba";
     const CHANNELS: usize = 4;
+    const ALPHA: bool = true;
+    const TO_RGB_MATRIX: Option<icc::Matrix> = Some(IDENTITY);
+    const FROM_RGB_MATRIX: Option<icc::Matrix> = Some(IDENTITY);
 
     fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
-        pixel[c] * pixel[3]
+        pixel[c]
     }
 
     fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
@@ -76,161 +460,746 @@ impl Color for Rgba {
     }
 }
 
-color!(Xyz);
+impl Premultiply for Rgba {
+    type Premultiplied = PremultipliedRgba;
+}
+
+color!(PremultipliedRgba);
+impl Color for PremultipliedRgba {
+    const NAME: &'static str = "premultiplied_rgba";
+    const CHANNELS: usize = 4;
+    const ALPHA: bool = true;
+    const PREMULTIPLIED: bool = true;
+
+    fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
+        unpremultiply(pixel[c], pixel[3])
+    }
+
+    fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+        Rgba::from_rgb(c, pixel)
+    }
+}
+
+impl Unpremultiply for PremultipliedRgba {
+    type Straight = Rgba;
+}
+
+color!(GrayAlpha);
+impl Color for GrayAlpha {
+    const NAME: &'static str = "graya";
+    const CHANNELS: usize = 2;
+    const ALPHA: bool = true;
+
+    fn to_rgb(_c: usize, pixel: &Pixel<Self>) -> f64 {
+        pixel[0]
+    }
+
+    fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+        if c == 1 {
+            return 1.0;
+        }
+
+        Gray::from_rgb(0, pixel)
+    }
+
+    fn from_rgb_pixel(rgb: [f64; 3], pixel: &mut Pixel<Self>) {
+        pixel.copy_from_slice(&[luma(rgb), 1.0]);
+    }
+}
+
+impl Premultiply for GrayAlpha {
+    type Premultiplied = PremultipliedGrayAlpha;
+}
+
+color!(PremultipliedGrayAlpha);
+impl Color for PremultipliedGrayAlpha {
+    const NAME: &'static str = "premultiplied_graya";
+    const CHANNELS: usize = 2;
+    const ALPHA: bool = true;
+    const PREMULTIPLIED: bool = true;
+
+    fn to_rgb(_c: usize, pixel: &Pixel<Self>) -> f64 {
+        unpremultiply(pixel[0], pixel[1])
+    }
+
+    fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+        GrayAlpha::from_rgb(c, pixel)
+    }
+}
+
+impl Unpremultiply for PremultipliedGrayAlpha {
+    type Straight = GrayAlpha;
+}
+
+color!(Bgr);
+impl Color for Bgr {
+    const NAME: &'static str = "bgr";
+    const CHANNELS: usize = 3;
+    const CHANNEL_ORDER: &'static [usize] = &[2, 1, 0];
+    const TO_RGB_MATRIX: Option<icc::Matrix> = Some(SWAP_RED_BLUE);
+    const FROM_RGB_MATRIX: Option<icc::Matrix> = Some(SWAP_RED_BLUE);
+
+    fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
+        pixel[2 - c]
+    }
+
+    fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+        pixel[2 - c]
+    }
+}
+
+color!(Bgra);
+impl Color for Bgra {
+    const NAME: &'static str = "bgra";
+    const CHANNELS: usize = 4;
+    const ALPHA: bool = true;
+    const CHANNEL_ORDER: &'static [usize] = &[2, 1, 0, 3];
+    const TO_RGB_MATRIX: Option<icc::Matrix> = Some(SWAP_RED_BLUE);
+    const FROM_RGB_MATRIX: Option<icc::Matrix> = Some(SWAP_RED_BLUE);
+
+    fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
+        pixel[2 - c]
+    }
+
+    fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+        if c == 3 {
+            return 1.0;
+        }
+
+        pixel[2 - c]
+    }
+}
+
+color!(Argb);
+impl Color for Argb {
+    const NAME: &'static str = "argb";
+    const CHANNELS: usize = 4;
+    const ALPHA: bool = true;
+    const ALPHA_CHANNEL: usize = 0;
+    const CHANNEL_ORDER: &'static [usize] = &[3, 0, 1, 2];
+    const TO_RGB_MATRIX: Option<icc::Matrix> = Some(IDENTITY);
+    const FROM_RGB_MATRIX: Option<icc::Matrix> = Some(IDENTITY);
+
+    fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
+        pixel[c + 1]
+    }
+
+    fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+        if c == 0 {
+            return 1.0;
+        }
+
+        pixel[c - 1]
+    }
+}
+
+/// Linear sRGB to CIE XYZ, D65 reference white
+const RGB_TO_XYZ: icc::Matrix = [
+    [0.4124564, 0.3575761, 0.1804375],
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.0193339, 0.1191920, 0.9503041],
+];
+
+/// CIE XYZ to linear sRGB, D65 reference white
+const XYZ_TO_RGB: icc::Matrix = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+
+/// Bradford cone response matrix
+const BRADFORD: icc::Matrix = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+
+/// Inverse of `BRADFORD`
+const BRADFORD_INV: icc::Matrix = [
+    [0.9869929, -0.1470543, 0.1599627],
+    [0.4323053, 0.5183603, 0.0492912],
+    [-0.0085287, 0.0400428, 0.9684867],
+];
+
+/// Adapt XYZ values from one reference white to another using the Bradford transform
+pub fn adapt_white_point(xyz: [f64; 3], from: &[f64; 3], to: &[f64; 3]) -> [f64; 3] {
+    if from == to {
+        return xyz;
+    }
+
+    let src = icc::mul(&BRADFORD, *from);
+    let dest = icc::mul(&BRADFORD, *to);
+    let lms = icc::mul(&BRADFORD, xyz);
+    icc::mul(
+        &BRADFORD_INV,
+        [
+            lms[0] * dest[0] / src[0],
+            lms[1] * dest[1] / src[1],
+            lms[2] * dest[2] / src[2],
+        ],
+    )
+}
+
+fn rgb_to_xyz(rgb: [f64; 3]) -> [f64; 3] {
+    icc::mul(&RGB_TO_XYZ, rgb)
+}
+
+fn xyz_to_rgb(xyz: [f64; 3]) -> [f64; 3] {
+    icc::mul(&XYZ_TO_RGB, xyz)
+}
+
+/// XYZ value stored as 1.0 by `Xyz`, matching the 16-bit ICC PCSXYZ encoding
+const XYZ_MAX: f64 = 65535.0 / 32768.0;
+
+/// `RGB_TO_XYZ` scaled to the `Xyz` channel range
+const RGB_TO_XYZ_NORM: icc::Matrix = [
+    [
+        RGB_TO_XYZ[0][0] / XYZ_MAX,
+        RGB_TO_XYZ[0][1] / XYZ_MAX,
+        RGB_TO_XYZ[0][2] / XYZ_MAX,
+    ],
+    [
+        RGB_TO_XYZ[1][0] / XYZ_MAX,
+        RGB_TO_XYZ[1][1] / XYZ_MAX,
+        RGB_TO_XYZ[1][2] / XYZ_MAX,
+    ],
+    [
+        RGB_TO_XYZ[2][0] / XYZ_MAX,
+        RGB_TO_XYZ[2][1] / XYZ_MAX,
+        RGB_TO_XYZ[2][2] / XYZ_MAX,
+    ],
+];
+
+/// `XYZ_TO_RGB` taking values in the `Xyz` channel range
+const XYZ_NORM_TO_RGB: icc::Matrix = [
+    [
+        XYZ_TO_RGB[0][0] * XYZ_MAX,
+        XYZ_TO_RGB[0][1] * XYZ_MAX,
+        XYZ_TO_RGB[0][2] * XYZ_MAX,
+    ],
+    [
+        XYZ_TO_RGB[1][0] * XYZ_MAX,
+        XYZ_TO_RGB[1][1] * XYZ_MAX,
+        XYZ_TO_RGB[1][2] * XYZ_MAX,
+    ],
+    [
+        XYZ_TO_RGB[2][0] * XYZ_MAX,
+        XYZ_TO_RGB[2][1] * XYZ_MAX,
+        XYZ_TO_RGB[2][2] * XYZ_MAX,
+    ],
+];
+
+color!(
+    /// CIE XYZ with a D65 reference white
+    ///
+    /// Channels are scaled by `32768 / 65535` like the ICC PCSXYZ encoding, so the Z value of
+    /// D65 white (1.089) fits in the range `0..=1`
+    Xyz
+);
 impl Color for Xyz {
     const NAME: &'static str = "xyz";
     const CHANNELS: usize = 3;
+    const TO_RGB_MATRIX: Option<icc::Matrix> = Some(XYZ_NORM_TO_RGB);
+    const FROM_RGB_MATRIX: Option<icc::Matrix> = Some(RGB_TO_XYZ_NORM);
 
     fn from_rgb(c: usize, rgb: &Pixel<Rgb>) -> f64 {
-        match c {
-            0 => rgb[0] * 0.576700 + rgb[1] * 0.185556 + rgb[2] * 0.188212,
-            1 => rgb[0] * 0.297361 + rgb[1] * 0.627355 + rgb[2] * 0.0752847,
-            2 => rgb[0] * 0.0270328 + rgb[1] * 0.0706879 + rgb[2] * 0.991248,
-            _ => 0.0,
-        }
+        icc::mul(&RGB_TO_XYZ_NORM, [rgb[0], rgb[1], rgb[2]])[c]
     }
 
     fn to_rgb(c: usize, px: &Pixel<Xyz>) -> f64 {
-        match c {
-            0 => 3.2404542 * px[0] - 1.5371385 * px[1] - 0.4985314 * px[2],
-            1 => -0.9692660 * px[0] + 1.8760108 * px[1] + 0.0415560 * px[2],
-            2 => 0.0556434 * px[0] - 0.2040259 * px[1] + 1.0572252 * px[2],
-            _ => 0.0,
+        icc::mul(&XYZ_NORM_TO_RGB, [px[0], px[1], px[2]])[c]
+    }
+}
+
+/// Reference white used by the CIE colors `Lab`, `Lch` and `Luv`
+pub trait WhitePoint:
+    'static + std::fmt::Debug + Unpin + PartialEq + Eq + PartialOrd + Ord + Clone + Sync + Send
+{
+    /// XYZ coordinates, normalized so that Y is 1.0
+    const XYZ: [f64; 3];
+}
+
+/// CIE standard illuminant D50, the white point of the ICC profile connection space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct D50;
+
+impl WhitePoint for D50 {
+    const XYZ: [f64; 3] = icc::D50;
+}
+
+/// CIE standard illuminant D65, the white point of sRGB and `Xyz`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct D65;
+
+impl WhitePoint for D65 {
+    const XYZ: [f64; 3] = [0.95047, 1.0, 1.08883];
+}
+
+/// Chroma value stored as 1.0 by `Lch`
+const LCH_MAX_CHROMA: f64 = 150.0;
+
+/// Convert RGB to unnormalized CIE Lab relative to `W`
+fn rgb_to_lab<W: WhitePoint>(rgb: [f64; 3]) -> [f64; 3] {
+    let xyz = adapt_white_point(rgb_to_xyz(rgb), &D65::XYZ, &W::XYZ);
+    icc::xyz_to_lab(xyz, &W::XYZ)
+}
+
+/// Convert unnormalized CIE Lab relative to `W` to RGB
+fn lab_to_rgb<W: WhitePoint>(lab: [f64; 3]) -> [f64; 3] {
+    let xyz = icc::lab_to_xyz(lab, &W::XYZ);
+    xyz_to_rgb(adapt_white_point(xyz, &W::XYZ, &D65::XYZ))
+}
+
+/// CIE L\*a\*b\* relative to the reference white `W`
+///
+/// Channels are normalized the same way as ICC Lab: `L / 100` and `(a + 128) / 255`,
+/// `(b + 128) / 255`
+///
+/// Like the other perceptual colors it's computed from linear light, read sRGB encoded files such
+/// as 8-bit JPEG or PNG images as `Srgb` so the values are decoded before converting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Lab<W: WhitePoint = D65>(std::marker::PhantomData<W>);
+
+impl<W: WhitePoint> Color for Lab<W> {
+    const NAME: &'static str = "lab";
+    const CHANNELS: usize = 3;
+
+    channels_from_pixel!();
+
+    fn to_rgb_pixel(px: &Pixel<Self>) -> [f64; 3] {
+        lab_to_rgb::<W>([px[0] * 100.0, px[1] * 255.0 - 128.0, px[2] * 255.0 - 128.0])
+    }
+
+    fn from_rgb_pixel(rgb: [f64; 3], px: &mut Pixel<Self>) {
+        let lab = rgb_to_lab::<W>(rgb);
+        px.copy_from_slice(&[
+            lab[0] / 100.0,
+            (lab[1] + 128.0) / 255.0,
+            (lab[2] + 128.0) / 255.0,
+        ]);
+    }
+}
+
+/// CIE LCh(ab), the cylindrical form of `Lab`, relative to the reference white `W`
+///
+/// Channels are normalized as `L / 100`, `C / 150` and `h / 360`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Lch<W: WhitePoint = D65>(std::marker::PhantomData<W>);
+
+impl<W: WhitePoint> Color for Lch<W> {
+    const NAME: &'static str = "lch";
+    const CHANNELS: usize = 3;
+
+    channels_from_pixel!();
+
+    fn to_rgb_pixel(px: &Pixel<Self>) -> [f64; 3] {
+        let chroma = px[1] * LCH_MAX_CHROMA;
+        let hue = (px[2] * 360.0).to_radians();
+        lab_to_rgb::<W>([px[0] * 100.0, chroma * hue.cos(), chroma * hue.sin()])
+    }
+
+    fn from_rgb_pixel(rgb: [f64; 3], px: &mut Pixel<Self>) {
+        let lab = rgb_to_lab::<W>(rgb);
+        px.copy_from_slice(&[
+            lab[0] / 100.0,
+            lab[1].hypot(lab[2]) / LCH_MAX_CHROMA,
+            lab[2].atan2(lab[1]).to_degrees().rem_euclid(360.0) / 360.0,
+        ]);
+    }
+}
+
+/// Get the u' and v' chromaticity coordinates of an XYZ color
+fn uv(xyz: [f64; 3]) -> (f64, f64) {
+    let d = xyz[0] + 15.0 * xyz[1] + 3.0 * xyz[2];
+    if d == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    (4.0 * xyz[0] / d, 9.0 * xyz[1] / d)
+}
+
+/// CIE L\*u\*v\* relative to the reference white `W`
+///
+/// Channels are normalized as `L / 100`, `(u + 134) / 354` and `(v + 140) / 262`, which covers
+/// the range of values produced by RGB colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Luv<W: WhitePoint = D65>(std::marker::PhantomData<W>);
+
+impl<W: WhitePoint> Color for Luv<W> {
+    const NAME: &'static str = "luv";
+    const CHANNELS: usize = 3;
+
+    channels_from_pixel!();
+
+    fn to_rgb_pixel(px: &Pixel<Self>) -> [f64; 3] {
+        let l = px[0] * 100.0;
+        if l <= 0.0 {
+            return [0.0; 3];
         }
+
+        let (un, vn) = uv(W::XYZ);
+        let u = (px[1] * 354.0 - 134.0) / (13.0 * l) + un;
+        let v = (px[2] * 262.0 - 140.0) / (13.0 * l) + vn;
+        let y = if l > icc::KAPPA * icc::EPSILON {
+            ((l + 16.0) / 116.0).powi(3)
+        } else {
+            l / icc::KAPPA
+        } * W::XYZ[1];
+        let xyz = [
+            y * 9.0 * u / (4.0 * v),
+            y,
+            y * (12.0 - 3.0 * u - 20.0 * v) / (4.0 * v),
+        ];
+        xyz_to_rgb(adapt_white_point(xyz, &W::XYZ, &D65::XYZ))
+    }
+
+    fn from_rgb_pixel(rgb: [f64; 3], px: &mut Pixel<Self>) {
+        let xyz = adapt_white_point(rgb_to_xyz(rgb), &D65::XYZ, &W::XYZ);
+        let y = xyz[1] / W::XYZ[1];
+        let l = if y > icc::EPSILON {
+            116.0 * y.cbrt() - 16.0
+        } else {
+            icc::KAPPA * y
+        };
+        let (u, v) = uv(xyz);
+        let (un, vn) = uv(W::XYZ);
+        px.copy_from_slice(&[
+            l / 100.0,
+            (13.0 * l * (u - un) + 134.0) / 354.0,
+            (13.0 * l * (v - vn) + 140.0) / 262.0,
+        ]);
     }
 }
 
-color!(Hsv);
+/// Convert linear sRGB to unnormalized Oklab
+fn rgb_to_oklab([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+/// Convert unnormalized Oklab to linear sRGB
+fn oklab_to_rgb(lab: [f64; 3]) -> [f64; 3] {
+    let l = (lab[0] + 0.3963377774 * lab[1] + 0.2158037573 * lab[2]).powi(3);
+    let m = (lab[0] - 0.1055613458 * lab[1] - 0.0638541728 * lab[2]).powi(3);
+    let s = (lab[0] - 0.0894841775 * lab[1] - 1.2914855480 * lab[2]).powi(3);
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ]
+}
+
+/// Chroma value stored as 1.0 by `Oklch`
+const OKLCH_MAX_CHROMA: f64 = 0.4;
+
+color!(
+    /// Oklab perceptual color space
+    ///
+    /// Channels are normalized as `L`, `a + 0.5` and `b + 0.5`, the `a` and `b` values of RGB
+    /// colors are always in the range `-0.5..=0.5`
+    Oklab
+);
+impl Color for Oklab {
+    const NAME: &'static str = "oklab";
+    const CHANNELS: usize = 3;
+
+    channels_from_pixel!();
+
+    fn to_rgb_pixel(px: &Pixel<Self>) -> [f64; 3] {
+        oklab_to_rgb([px[0], px[1] - 0.5, px[2] - 0.5])
+    }
+
+    fn from_rgb_pixel(rgb: [f64; 3], px: &mut Pixel<Self>) {
+        let lab = rgb_to_oklab(rgb);
+        px.copy_from_slice(&[lab[0], lab[1] + 0.5, lab[2] + 0.5]);
+    }
+}
+
+color!(
+    /// Cylindrical form of `Oklab`
+    ///
+    /// Channels are normalized as `L`, `C / 0.4` and `h / 360`
+    Oklch
+);
+impl Color for Oklch {
+    const NAME: &'static str = "oklch";
+    const CHANNELS: usize = 3;
+
+    channels_from_pixel!();
+
+    fn to_rgb_pixel(px: &Pixel<Self>) -> [f64; 3] {
+        let chroma = px[1] * OKLCH_MAX_CHROMA;
+        let hue = (px[2] * 360.0).to_radians();
+        oklab_to_rgb([px[0], chroma * hue.cos(), chroma * hue.sin()])
+    }
+
+    fn from_rgb_pixel(rgb: [f64; 3], px: &mut Pixel<Self>) {
+        let lab = rgb_to_oklab(rgb);
+        px.copy_from_slice(&[
+            lab[0],
+            lab[1].hypot(lab[2]) / OKLCH_MAX_CHROMA,
+            lab[2].atan2(lab[1]).to_degrees().rem_euclid(360.0) / 360.0,
+        ]);
+    }
+}
+
+color!(
+    /// Hue, saturation and value
+    ///
+    /// All channels are in the range `0..=1`, hue is stored as `h / 360`
+    Hsv
+);
 impl Color for Hsv {
     const NAME: &'static str = "hsv";
     const CHANNELS: usize = 3;
 
-    fn from_rgb(c: usize, rgb: &Pixel<Rgb>) -> f64 {
-        let r = rgb[0];
-        let g = rgb[1];
-        let b = rgb[2];
+    channels_from_pixel!();
+
+    fn from_rgb_pixel([r, g, b]: [f64; 3], px: &mut Pixel<Self>) {
         let cmax = r.max(g).max(b);
         let cmin = r.min(g).min(b);
         let delta = cmax - cmin;
-        match c {
-            0 => {
-                if cmin == cmax {
-                    0.0
-                } else if cmax == r {
-                    (60. * ((g - b) / delta) + 360.0) % 360.
-                } else if cmax == g {
-                    (60. * ((b - r) / delta) + 120.0) % 360.
-                } else if cmax == b {
-                    (60. * ((r - g) / delta) + 240.0) % 360.
-                } else {
-                    -1.0
-                }
-            }
-            1 => {
-                if cmax == 0.0 {
-                    0.0
-                } else {
-                    (delta / cmax) * 100.0
-                }
-            }
-            2 => cmax * 100.,
-            _ => -1.0,
-        }
+        let h = if cmin == cmax {
+            0.0
+        } else if cmax == r {
+            (60. * ((g - b) / delta) + 360.0) % 360.
+        } else if cmax == g {
+            (60. * ((b - r) / delta) + 120.0) % 360.
+        } else {
+            (60. * ((r - g) / delta) + 240.0) % 360.
+        };
+
+        let s = if cmax == 0.0 { 0.0 } else { delta / cmax };
+        px.copy_from_slice(&[h / 360., s, cmax]);
     }
 
-    fn to_rgb(c: usize, px: &Pixel<Hsv>) -> f64 {
-        if px[1] == 0. {
-            px[2]
+    fn to_rgb_pixel(px: &Pixel<Hsv>) -> [f64; 3] {
+        let (h, s, v) = (px[0], px[1], px[2]);
+        if s == 0. {
+            return [v; 3];
+        }
+
+        let mut var_h = h * 6.;
+        if var_h == 6. {
+            var_h = 0.0;
+        }
+        let var_i = var_h.floor();
+        let var_1 = v * (1. - s);
+        let var_2 = v * (1. - s * (var_h - var_i));
+        let var_3 = v * (1. - s * (1. - (var_h - var_i)));
+
+        if var_i == 0. {
+            [v, var_3, var_1]
+        } else if var_i == 1. {
+            [var_2, v, var_1]
+        } else if var_i == 2. {
+            [var_1, v, var_3]
+        } else if var_i == 3. {
+            [var_1, var_2, v]
+        } else if var_i == 4. {
+            [var_3, var_1, v]
         } else {
-            let (h, s, v) = (px[0], px[1], px[2]);
-            let mut var_h = h * 6.;
-            if var_h == 6. {
-                var_h = 0.0;
-            }
-            let var_i = var_h.floor();
-            let var_1 = v * (1. - s);
-            let var_2 = v * (1. - s * (var_h - var_i));
-            let var_3 = v * (1. - s * (1. - (var_h - var_i)));
-
-            if var_i == 0. {
-                match c {
-                    0 => v,
-                    1 => var_3,
-                    2 => var_1,
-                    _ => 0.0,
-                }
-            } else if var_i == 1. {
-                match c {
-                    0 => var_2,
-                    1 => v,
-                    2 => var_1,
-                    _ => 0.0,
-                }
-            } else if var_i == 2. {
-                match c {
-                    0 => var_1,
-                    1 => v,
-                    2 => var_3,
-                    _ => 0.0,
-                }
-            } else if var_i == 3. {
-                match c {
-                    0 => var_1,
-                    1 => var_2,
-                    2 => v,
-                    _ => 0.0,
-                }
-            } else if var_i == 4. {
-                match c {
-                    0 => var_3,
-                    1 => var_1,
-                    2 => v,
-                    _ => 0.0,
-                }
-            } else {
-                match c {
-                    0 => v,
-                    1 => var_1,
-                    2 => var_2,
-                    _ => 0.0,
-                }
-            }
+            [v, var_1, var_2]
         }
     }
 }
 
-color!(Yuv);
+color!(
+    /// Hue, saturation and lightness
+    ///
+    /// All channels are in the range `0..=1`, hue is stored as `h / 360`
+    Hsl
+);
+impl Color for Hsl {
+    const NAME: &'static str = "hsl";
+    const CHANNELS: usize = 3;
+
+    channels_from_pixel!();
+
+    fn from_rgb_pixel([r, g, b]: [f64; 3], px: &mut Pixel<Self>) {
+        let cmax = r.max(g).max(b);
+        let cmin = r.min(g).min(b);
+        let delta = cmax - cmin;
+        let l = (cmax + cmin) / 2.0;
+        let h = if delta == 0.0 {
+            0.0
+        } else if cmax == r {
+            ((g - b) / delta).rem_euclid(6.0)
+        } else if cmax == g {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        };
+
+        let s = if delta == 0.0 {
+            0.0
+        } else {
+            delta / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        px.copy_from_slice(&[h / 6.0, s, l]);
+    }
+
+    fn to_rgb_pixel(px: &Pixel<Self>) -> [f64; 3] {
+        let (h, s, l) = (px[0], px[1], px[2]);
+        let a = s * l.min(1.0 - l);
+        let f = |n: f64| {
+            let k = (n + h * 12.0).rem_euclid(12.0);
+            l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+        };
+        [f(0.0), f(8.0), f(4.0)]
+    }
+}
+
+color!(
+    /// Analog YUV using the BT.601 luma coefficients
+    ///
+    /// U and V are scaled to `0..=1` and centered at 0.5
+    Yuv
+);
 impl Color for Yuv {
     const NAME: &'static str = "yuv";
     const CHANNELS: usize = 3;
 
-    fn from_rgb(c: usize, rgb: &Pixel<Rgb>) -> f64 {
-        let r = rgb[0];
-        let g = rgb[1];
-        let b = rgb[2];
+    channels_from_pixel!();
 
-        match c {
-            0 => 0.299 * r + 0.587 * g + 0.114 * b,
-            1 => -0.147 * r + 0.289 + g + 0.436 * b,
-            2 => 0.615 * r + 0.515 * g + 0.1 * b,
-            _ => 0.0,
-        }
+    fn from_rgb_pixel([r, g, b]: [f64; 3], px: &mut Pixel<Self>) {
+        let y = 0.299 * r + 0.587 * g + 0.114 * b;
+        px.copy_from_slice(&[
+            y,
+            (b - y) / (2.0 * 0.886) + 0.5,
+            (r - y) / (2.0 * 0.701) + 0.5,
+        ]);
     }
 
-    fn to_rgb(c: usize, px: &Pixel<Self>) -> f64 {
+    fn to_rgb_pixel(px: &Pixel<Self>) -> [f64; 3] {
         let y = px[0];
-        let u = px[1];
-        let v = px[2];
-        match c {
-            0 => y + 1.14 * v,
-            1 => y - 0.395 * u - 0.581 * v,
-            2 => y + 2.032 * u,
-            _ => 0.0,
+        let r = y + (px[2] - 0.5) * 2.0 * 0.701;
+        let b = y + (px[1] - 0.5) * 2.0 * 0.886;
+        [r, (y - 0.299 * r - 0.114 * b) / 0.587, b]
+    }
+}
+
+/// Luma coefficients used by `YCbCr`
+pub trait YCbCrStandard:
+    'static + std::fmt::Debug + Unpin + PartialEq + Eq + PartialOrd + Ord + Clone + Sync + Send
+{
+    /// Red coefficient
+    const KR: f64;
+
+    /// Blue coefficient
+    const KB: f64;
+}
+
+/// ITU-R BT.601, used by JPEG and standard definition video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bt601;
+
+impl YCbCrStandard for Bt601 {
+    const KR: f64 = 0.299;
+    const KB: f64 = 0.114;
+}
+
+/// ITU-R BT.709, used by high definition video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bt709;
+
+impl YCbCrStandard for Bt709 {
+    const KR: f64 = 0.2126;
+    const KB: f64 = 0.0722;
+}
+
+/// ITU-R BT.2020, used by ultra high definition video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bt2020;
+
+impl YCbCrStandard for Bt2020 {
+    const KR: f64 = 0.2627;
+    const KB: f64 = 0.0593;
+}
+
+/// Range of values used by `YCbCr`
+pub trait YCbCrRange:
+    'static + std::fmt::Debug + Unpin + PartialEq + Eq + PartialOrd + Ord + Clone + Sync + Send
+{
+    /// True for studio range: 16-235 for Y and 16-240 for Cb and Cr, on the 8-bit scale
+    const LIMITED: bool;
+}
+
+/// Y, Cb and Cr use all available values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FullRange;
+
+impl YCbCrRange for FullRange {
+    const LIMITED: bool = false;
+}
+
+/// Studio range, Y uses 16-235 and Cb and Cr use 16-240 on the 8-bit scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LimitedRange;
+
+impl YCbCrRange for LimitedRange {
+    const LIMITED: bool = true;
+}
+
+/// Digital YCbCr using the luma coefficients from `S` and the range of values from `R`
+///
+/// Channels are normalized on the 8-bit scale: full range Y is in `0..=1` with Cb and Cr centered
+/// at `128/255` like JPEG, limited range Y is in `16/255..=235/255` and Cb and Cr are in
+/// `16/255..=240/255`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct YCbCr<S: YCbCrStandard = Bt601, R: YCbCrRange = FullRange>(
+    std::marker::PhantomData<(S, R)>,
+);
+
+impl<S: YCbCrStandard, R: YCbCrRange> Color for YCbCr<S, R> {
+    const NAME: &'static str = "ycbcr";
+    const CHANNELS: usize = 3;
+
+    channels_from_pixel!();
+
+    fn from_rgb_pixel([r, g, b]: [f64; 3], px: &mut Pixel<Self>) {
+        let y = S::KR * r + (1.0 - S::KR - S::KB) * g + S::KB * b;
+        let cb = (b - y) / (2.0 * (1.0 - S::KB));
+        let cr = (r - y) / (2.0 * (1.0 - S::KR));
+
+        if R::LIMITED {
+            px.copy_from_slice(&[
+                (16.0 + 219.0 * y) / 255.0,
+                (128.0 + 224.0 * cb) / 255.0,
+                (128.0 + 224.0 * cr) / 255.0,
+            ]);
+        } else {
+            px.copy_from_slice(&[y, cb + 128.0 / 255.0, cr + 128.0 / 255.0]);
         }
     }
+
+    fn to_rgb_pixel(px: &Pixel<Self>) -> [f64; 3] {
+        let (y, cb, cr) = if R::LIMITED {
+            (
+                (px[0] * 255.0 - 16.0) / 219.0,
+                (px[1] * 255.0 - 128.0) / 224.0,
+                (px[2] * 255.0 - 128.0) / 224.0,
+            )
+        } else {
+            (px[0], px[1] - 128.0 / 255.0, px[2] - 128.0 / 255.0)
+        };
+
+        let r = y + 2.0 * (1.0 - S::KR) * cr;
+        let b = y + 2.0 * (1.0 - S::KB) * cb;
+        [r, (y - S::KR * r - S::KB * b) / (1.0 - S::KR - S::KB), b]
+    }
 }
 
-color!(Cmyk);
+color!(
+    /// Naive CMYK without a print profile, all channels are in the range `0..=1`
+    Cmyk
+);
 impl Color for Cmyk {
     const NAME: &'static str = "cmyk";
     const CHANNELS: usize = 4;
@@ -240,29 +1209,85 @@ impl Color for Cmyk {
         let g = rgb[1];
         let b = rgb[2];
         let k = 1.0 - r.max(g).max(b);
+        if k >= 1.0 {
+            return if c == 3 { 1.0 } else { 0.0 };
+        }
+
         match c {
             0 => (1. - r - k) / (1. - k),
             1 => (1. - g - k) / (1. - k),
             2 => (1. - b - k) / (1.0 - k),
-            4 => k,
+            3 => k,
             _ => 0.0,
         }
     }
 
+    fn from_rgb_pixel([r, g, b]: [f64; 3], cmyk: &mut Pixel<Self>) {
+        let k = 1.0 - r.max(g).max(b);
+        if k >= 1.0 {
+            cmyk.copy_from_slice(&[0.0, 0.0, 0.0, 1.0]);
+            return;
+        }
+
+        cmyk.copy_from_slice(&[
+            (1. - r - k) / (1. - k),
+            (1. - g - k) / (1. - k),
+            (1. - b - k) / (1. - k),
+            k,
+        ]);
+    }
+
     fn to_rgb(i: usize, cmyk: &Pixel<Cmyk>) -> f64 {
         let c = cmyk[0];
         let m = cmyk[1];
         let y = cmyk[2];
         let k = cmyk[3];
         match i {
-            0 => (1. - c / 100.) * (1. - k / 100.),
-            1 => (1. - m / 100.) * (1. - k / 100.),
-            2 => (1. - y / 100.) * (1. - k / 100.),
+            0 => (1. - c) * (1. - k),
+            1 => (1. - m) * (1. - k),
+            2 => (1. - y) * (1. - k),
             _ => 0.0,
         }
     }
 }
 
+/// Color with an arbitrary number of channels, used for multispectral data
+///
+/// `to_rgb` uses the first band as gray when there are less than 3 bands, otherwise the first
+/// three bands are used as red, green and blue. `from_rgb` is the inverse, with any remaining
+/// bands set to 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Channels<const N: usize>;
+
+impl<const N: usize> Color for Channels<N> {
+    const NAME: &'static str = "channels";
+    const CHANNELS: usize = N;
+    const TO_RGB_MATRIX: Option<icc::Matrix> = if N >= 3 { Some(IDENTITY) } else { None };
+    const FROM_RGB_MATRIX: Option<icc::Matrix> = Self::TO_RGB_MATRIX;
+
+    fn to_rgb(c: usize, pixel: &Pixel<Self>) -> f64 {
+        if N < 3 {
+            pixel[0]
+        } else {
+            pixel[c]
+        }
+    }
+
+    fn from_rgb(c: usize, pixel: &Pixel<Rgb>) -> f64 {
+        if N < 3 {
+            if c == 0 {
+                Gray::from_rgb(0, pixel)
+            } else {
+                0.0
+            }
+        } else if c < 3 {
+            pixel[c]
+        } else {
+            0.0
+        }
+    }
+}
+
 #[derive(Clone, Copy, Default)]
 pub struct Convert<T: Color>(std::marker::PhantomData<T>);
 
@@ -273,13 +1298,37 @@ impl<C: Color> Convert<C> {
 }
 
 impl<T: Color> Filter for Convert<T> {
-    fn compute_at(
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        Color::convert::<T>(c, &input[0].get_pixel(x, y))
+    }
+
+    fn compute_pixel<C: Color>(
         &self,
         x: usize,
         y: usize,
-        c: usize,
-        input: &[&Image<impl Type, impl Color>],
-    ) -> f64 {
-        Color::convert::<T>(c, &input[0].get_pixel(x, y))
+        input: &[&impl GenericImage],
+        out: &mut Pixel<C>,
+    ) {
+        convert_at::<T, _, _>(input[0], x, y, out)
+    }
+}
+
+/// Convert the pixel at (x, y) of `input` to `T` as a whole, storing the result in `out`
+#[inline]
+fn convert_at<T: Color, I: GenericImage, C: Color>(
+    input: &I,
+    x: usize,
+    y: usize,
+    out: &mut Pixel<C>,
+) {
+    let src = input.get_pixel(x, y);
+    let mut dest = Pixel::<T>::new();
+    match conversion_matrix::<I::C, T>() {
+        Some(m) => convert_pixel_matrix(&m, &src, &mut dest),
+        None => I::C::convert_pixel(&src, &mut dest),
+    }
+
+    for (a, b) in out.iter_mut().zip(dest.iter()) {
+        *a = *b;
     }
 }
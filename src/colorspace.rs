@@ -0,0 +1,201 @@
+//! Named RGB colorspaces
+//!
+//! A small built-in registry of colorspaces defined by their primaries, white point and transfer
+//! function, used by `Image::convert_colorspace`. Names are matched ignoring case, spaces,
+//! dashes, underscores and periods.
+
+use crate::*;
+
+/// An RGB colorspace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Colorspace {
+    /// Canonical name
+    pub name: &'static str,
+
+    /// Other names accepted by `Colorspace::find`
+    pub aliases: &'static [&'static str],
+
+    /// CIE xy chromaticity of the red, green and blue primaries
+    pub primaries: [[f64; 2]; 3],
+
+    /// CIE xy chromaticity of the white point
+    pub white: [f64; 2],
+
+    /// Transfer function used to encode values
+    pub transfer: TransferFunction,
+}
+
+const SRGB_PRIMARIES: [[f64; 2]; 3] = [[0.64, 0.33], [0.30, 0.60], [0.15, 0.06]];
+const D65_WHITE: [f64; 2] = [0.3127, 0.3290];
+const ACES_WHITE: [f64; 2] = [0.32168, 0.33767];
+
+/// Built-in colorspaces
+pub const COLORSPACES: &[Colorspace] = &[
+    Colorspace {
+        name: "sRGB",
+        aliases: &["srgb_texture"],
+        primaries: SRGB_PRIMARIES,
+        white: D65_WHITE,
+        transfer: TransferFunction::Srgb,
+    },
+    Colorspace {
+        name: "linear sRGB",
+        aliases: &["linear", "lnf", "lin_srgb", "linear_rec709", "scene_linear"],
+        primaries: SRGB_PRIMARIES,
+        white: D65_WHITE,
+        transfer: TransferFunction::Linear,
+    },
+    Colorspace {
+        name: "Rec.709",
+        aliases: &["bt709"],
+        primaries: SRGB_PRIMARIES,
+        white: D65_WHITE,
+        transfer: TransferFunction::Rec709,
+    },
+    Colorspace {
+        name: "Rec.2020",
+        aliases: &["bt2020"],
+        primaries: [[0.708, 0.292], [0.170, 0.797], [0.131, 0.046]],
+        white: D65_WHITE,
+        transfer: TransferFunction::Rec709,
+    },
+    Colorspace {
+        name: "Display P3",
+        aliases: &["p3", "p3_d65"],
+        primaries: [[0.680, 0.320], [0.265, 0.690], [0.150, 0.060]],
+        white: D65_WHITE,
+        transfer: TransferFunction::Srgb,
+    },
+    Colorspace {
+        name: "Adobe RGB",
+        aliases: &["adobe_rgb_1998"],
+        primaries: [[0.64, 0.33], [0.21, 0.71], [0.15, 0.06]],
+        white: D65_WHITE,
+        transfer: TransferFunction::Gamma(563.0 / 256.0),
+    },
+    Colorspace {
+        name: "ACES2065-1",
+        aliases: &["aces", "ap0"],
+        primaries: [[0.7347, 0.2653], [0.0, 1.0], [0.0001, -0.077]],
+        white: ACES_WHITE,
+        transfer: TransferFunction::Linear,
+    },
+    Colorspace {
+        name: "ACEScg",
+        aliases: &["ap1"],
+        primaries: [[0.713, 0.293], [0.165, 0.830], [0.128, 0.044]],
+        white: ACES_WHITE,
+        transfer: TransferFunction::Linear,
+    },
+];
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_' | '.'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn xy_to_xyz(xy: [f64; 2]) -> [f64; 3] {
+    [xy[0] / xy[1], 1.0, (1.0 - xy[0] - xy[1]) / xy[1]]
+}
+
+impl Colorspace {
+    /// Find a built-in colorspace by name or alias
+    pub fn find(name: &str) -> Option<&'static Colorspace> {
+        let name = normalize_name(name);
+        COLORSPACES.iter().find(|cs| {
+            normalize_name(cs.name) == name || cs.aliases.iter().any(|a| normalize_name(a) == name)
+        })
+    }
+
+    /// Find a built-in colorspace by name or alias, returning `Error::UnknownColorspace` if it
+    /// doesn't exist
+    pub fn get(name: &str) -> Result<&'static Colorspace, Error> {
+        Colorspace::find(name).ok_or_else(|| Error::UnknownColorspace(name.into()))
+    }
+
+    /// Matrix converting linear RGB to XYZ relative to the colorspace white point
+    pub fn to_xyz(&self) -> icc::Matrix {
+        let [r, g, b] = [
+            xy_to_xyz(self.primaries[0]),
+            xy_to_xyz(self.primaries[1]),
+            xy_to_xyz(self.primaries[2]),
+        ];
+        let m = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+        let s = icc::mul(
+            &icc::invert(&m).expect("Invalid primaries"),
+            xy_to_xyz(self.white),
+        );
+        [
+            [m[0][0] * s[0], m[0][1] * s[1], m[0][2] * s[2]],
+            [m[1][0] * s[0], m[1][1] * s[1], m[1][2] * s[2]],
+            [m[2][0] * s[0], m[2][1] * s[1], m[2][2] * s[2]],
+        ]
+    }
+}
+
+/// Converts values between two colorspaces, using Bradford adaptation when the white points
+/// differ
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    matrix: icc::Matrix,
+    from: TransferFunction,
+    to: TransferFunction,
+}
+
+impl Conversion {
+    /// Create a conversion between two colorspaces
+    pub fn new(from: &Colorspace, to: &Colorspace) -> Conversion {
+        let src = from.to_xyz();
+        let dest = icc::invert(&to.to_xyz()).expect("Invalid primaries");
+        let (from_white, to_white) = (xy_to_xyz(from.white), xy_to_xyz(to.white));
+
+        // Each step is linear, so the combined matrix is built from the basis vectors
+        let mut matrix = [[0.0; 3]; 3];
+        for i in 0..3 {
+            let mut rgb = [0.0; 3];
+            rgb[i] = 1.0;
+            let xyz = adapt_white_point(icc::mul(&src, rgb), &from_white, &to_white);
+            let col = icc::mul(&dest, xyz);
+            for (row, x) in matrix.iter_mut().zip(col.iter()) {
+                row[i] = *x;
+            }
+        }
+
+        Conversion {
+            matrix,
+            from: from.transfer,
+            to: to.transfer,
+        }
+    }
+
+    /// Create a conversion between two named colorspaces
+    pub fn named(from: &str, to: &str) -> Result<Conversion, Error> {
+        Ok(Conversion::new(
+            Colorspace::get(from)?,
+            Colorspace::get(to)?,
+        ))
+    }
+
+    /// Convert normalized, encoded RGB values
+    pub fn convert(&self, rgb: [f64; 3]) -> [f64; 3] {
+        let linear = [
+            self.from.decode(rgb[0]),
+            self.from.decode(rgb[1]),
+            self.from.decode(rgb[2]),
+        ];
+        let x = icc::mul(&self.matrix, linear);
+        [
+            self.to.encode(x[0]),
+            self.to.encode(x[1]),
+            self.to.encode(x[2]),
+        ]
+    }
+
+    /// Convert a single normalized value using only the transfer functions, used for images
+    /// with less than 3 color channels
+    pub fn convert_value(&self, x: f64) -> f64 {
+        self.to.encode(self.from.decode(x))
+    }
+}
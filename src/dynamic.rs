@@ -0,0 +1,269 @@
+use std::any::Any;
+
+use crate::*;
+
+/// Invokes `$m` with the list of `DynamicImage` variants
+macro_rules! variants {
+    ($m:ident!($($args:tt)*)) => {
+        $m! {
+            ($($args)*)
+            GrayU8(u8, Gray) GrayAlphaU8(u8, GrayAlpha) RgbU8(u8, Rgb) RgbaU8(u8, Rgba)
+            GrayI8(i8, Gray) GrayAlphaI8(i8, GrayAlpha) RgbI8(i8, Rgb) RgbaI8(i8, Rgba)
+            GrayU16(u16, Gray) GrayAlphaU16(u16, GrayAlpha) RgbU16(u16, Rgb) RgbaU16(u16, Rgba)
+            GrayI16(i16, Gray) GrayAlphaI16(i16, GrayAlpha) RgbI16(i16, Rgb) RgbaI16(i16, Rgba)
+            GrayU32(u32, Gray) GrayAlphaU32(u32, GrayAlpha) RgbU32(u32, Rgb) RgbaU32(u32, Rgba)
+            GrayI32(i32, Gray) GrayAlphaI32(i32, GrayAlpha) RgbI32(i32, Rgb) RgbaI32(i32, Rgba)
+            GrayU64(u64, Gray) GrayAlphaU64(u64, GrayAlpha) RgbU64(u64, Rgb) RgbaU64(u64, Rgba)
+            GrayI64(i64, Gray) GrayAlphaI64(i64, GrayAlpha) RgbI64(i64, Rgb) RgbaI64(i64, Rgba)
+            GrayF16(f16, Gray) GrayAlphaF16(f16, GrayAlpha) RgbF16(f16, Rgb) RgbaF16(f16, Rgba)
+            GrayF32(f32, Gray) GrayAlphaF32(f32, GrayAlpha) RgbF32(f32, Rgb) RgbaF32(f32, Rgba)
+            GrayF64(f64, Gray) GrayAlphaF64(f64, GrayAlpha) RgbF64(f64, Rgb) RgbaF64(f64, Rgba)
+        }
+    };
+}
+
+macro_rules! define {
+    (() $($name:ident($t:ty, $c:ty))*) => {
+        /// An image with type and color determined at runtime
+        ///
+        /// Only `Gray` and `Rgb` based colors are used, so `open` and `save` keep the stored values
+        /// the same way `Image::open` and `Image::save` do for those colors
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum DynamicImage {
+            $($name(Image<$t, $c>),)*
+        }
+
+        impl DynamicImage {
+            /// Create a new image with the given storage type and number of channels
+            pub fn new(
+                base: io::BaseType,
+                channels: usize,
+                width: usize,
+                height: usize,
+            ) -> Result<DynamicImage, Error> {
+                $(
+                    if base == <$t as Type>::BASE && channels == <$c as Color>::CHANNELS {
+                        return Ok(DynamicImage::$name(Image::new(width, height)));
+                    }
+                )*
+                Err(Error::UnsupportedFormat(base, channels))
+            }
+        }
+
+        $(
+            impl From<Image<$t, $c>> for DynamicImage {
+                fn from(image: Image<$t, $c>) -> DynamicImage {
+                    DynamicImage::$name(image)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! dispatch_variants {
+    (($self:expr, $image:ident, $e:expr) $($name:ident($t:ty, $c:ty))*) => {
+        match $self {
+            $(DynamicImage::$name($image) => $e,)*
+        }
+    };
+}
+
+/// Evaluate `$e` with `$image` bound to the typed image
+macro_rules! dispatch {
+    ($self:expr, $image:ident => $e:expr) => {
+        variants!(dispatch_variants!($self, $image, $e))
+    };
+}
+
+variants!(define!());
+
+impl DynamicImage {
+    /// Open an image from disk using the type and number of channels stored in the file
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<DynamicImage, Error> {
+        Self::open_with(path, &io::OpenOptions::default())
+    }
+
+    /// Open an image from disk using the given options
+    pub fn open_with(
+        path: impl AsRef<std::path::Path>,
+        options: &io::OpenOptions,
+    ) -> Result<DynamicImage, Error> {
+        let path = path.as_ref();
+
+        #[cfg(feature = "oiio")]
+        let mut image = {
+            let input = io::Input::open(path)?;
+            let spec = input.spec();
+            let mut image = DynamicImage::new(spec.format(), spec.nchannels(), 0, 0)?;
+            dispatch!(&mut image, image => *image = input.read()?);
+            image
+        };
+
+        #[cfg(not(feature = "oiio"))]
+        let mut image = {
+            let (base, channels) = io::magick::get_image_format(path)?;
+            let mut image = DynamicImage::new(base, channels, 0, 0)?;
+            dispatch!(&mut image, image => *image = io::magick::read(path)?);
+            image
+        };
+
+        dispatch!(&mut image, image => image.read_file_metadata(path, options));
+        Ok(image)
+    }
+
+    /// Save an image to disk using the stored type and color
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        dispatch!(self, image => image.save(path))
+    }
+
+    /// Get a reference to the underlying image if the type and color match
+    pub fn as_typed<T: 'static + Type, C: 'static + Color>(&self) -> Option<&Image<T, C>> {
+        dispatch!(self, image => (image as &dyn Any).downcast_ref())
+    }
+
+    /// Get a mutable reference to the underlying image if the type and color match
+    pub fn as_typed_mut<T: 'static + Type, C: 'static + Color>(
+        &mut self,
+    ) -> Option<&mut Image<T, C>> {
+        dispatch!(self, image => (image as &mut dyn Any).downcast_mut())
+    }
+
+    /// Get the underlying image, converting only when the type or color don't match
+    pub fn into_typed<T: 'static + Type, C: 'static + Color>(self) -> Image<T, C> {
+        dispatch!(self, image => {
+            let mut image = Some(image);
+            match (&mut image as &mut dyn Any).downcast_mut::<Option<Image<T, C>>>() {
+                Some(typed) => typed.take().unwrap(),
+                None => image.unwrap().convert(),
+            }
+        })
+    }
+
+    /// Convert to the given type and color
+    pub fn convert<T: Type, C: Color>(&self) -> Image<T, C> {
+        dispatch!(self, image => image.convert())
+    }
+
+    /// Image width
+    pub fn width(&self) -> usize {
+        dispatch!(self, image => image.width())
+    }
+
+    /// Image height
+    pub fn height(&self) -> usize {
+        dispatch!(self, image => image.height())
+    }
+
+    /// Returns the number of channels
+    pub fn channels(&self) -> usize {
+        dispatch!(self, image => image.channels())
+    }
+
+    /// Returns (width, height, channels)
+    pub fn shape(&self) -> (usize, usize, usize) {
+        dispatch!(self, image => image.shape())
+    }
+
+    /// Storage type
+    pub fn base_type(&self) -> io::BaseType {
+        fn base<T: Type, C: Color>(_: &Image<T, C>) -> io::BaseType {
+            T::BASE
+        }
+        dispatch!(self, image => base(image))
+    }
+
+    /// Storage type name
+    pub fn type_name(&self) -> &str {
+        dispatch!(self, image => image.meta.type_name())
+    }
+
+    /// Color name
+    pub fn color_name(&self) -> &str {
+        dispatch!(self, image => image.meta.color_name())
+    }
+
+    /// Get a normalized float value
+    pub fn get_f(&self, x: usize, y: usize, c: usize) -> f64 {
+        dispatch!(self, image => image.get_f(x, y, c))
+    }
+
+    /// Set normalized float value
+    pub fn set_f(&mut self, x: usize, y: usize, c: usize, f: f64) {
+        dispatch!(self, image => image.set_f(x, y, c, f))
+    }
+
+    /// Raw image data
+    pub fn buffer(&self) -> &[u8] {
+        dispatch!(self, image => image.buffer())
+    }
+
+    /// Copy a region of an image to a new image
+    pub fn crop(&self, roi: Region) -> DynamicImage {
+        dispatch!(self, image => image.crop(roi).into())
+    }
+
+    /// Mirror the image horizontally
+    pub fn flip_horizontal(&self) -> DynamicImage {
+        dispatch!(self, image => image.flip_horizontal().into())
+    }
+
+    /// Mirror the image vertically
+    pub fn flip_vertical(&self) -> DynamicImage {
+        dispatch!(self, image => image.flip_vertical().into())
+    }
+
+    /// Rotate the image 90 degrees clockwise
+    pub fn rotate90(&self) -> DynamicImage {
+        dispatch!(self, image => image.rotate90().into())
+    }
+
+    /// Rotate the image 180 degrees
+    pub fn rotate180(&self) -> DynamicImage {
+        dispatch!(self, image => image.rotate180().into())
+    }
+
+    /// Rotate the image 270 degrees clockwise
+    pub fn rotate270(&self) -> DynamicImage {
+        dispatch!(self, image => image.rotate270().into())
+    }
+
+    /// Apply an EXIF orientation using lossless flips and rotations
+    pub fn orient(&self, orientation: io::exif::Orientation) -> DynamicImage {
+        dispatch!(self, image => image.orient(orientation).into())
+    }
+
+    /// Apply a filter, writing the result into a new image with the same type and color
+    pub fn apply(&self, filter: impl Filter) -> DynamicImage {
+        dispatch!(self, image => {
+            let mut dest = image.new_like();
+            filter.eval(&mut dest, &[image]);
+            dest.into()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_dynamic_image() {
+        let mut image: Image<u16, Rgb> = Image::new(4, 3);
+        image.set(1, 2, [1, 2, 3]);
+
+        let dynamic = DynamicImage::from(image.clone());
+        assert_eq!(dynamic.base_type(), io::BaseType::UInt16);
+        assert_eq!(dynamic.shape(), (4, 3, 3));
+        assert_eq!(dynamic.color_name(), "rgb");
+        assert!(dynamic.as_typed::<u16, Rgb>().is_some());
+        assert!(dynamic.as_typed::<u8, Rgb>().is_none());
+        assert_eq!(dynamic.rotate90().shape(), (3, 4, 3));
+        assert_eq!(dynamic.clone().into_typed::<u16, Rgb>(), image);
+        assert_eq!(dynamic.into_typed::<f32, Gray>().shape(), (4, 3, 1));
+
+        let half = DynamicImage::new(io::BaseType::Half, 4, 2, 2).unwrap();
+        assert!(matches!(half, DynamicImage::RgbaF16(_)));
+        assert!(DynamicImage::new(io::BaseType::UInt8, 5, 2, 2).is_err());
+        assert!(DynamicImage::new(io::BaseType::String, 3, 2, 2).is_err());
+    }
+}
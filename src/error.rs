@@ -15,15 +15,33 @@ pub enum Error {
     #[error("Invalid image dimensions: width={0}, height={1}, channels={2}")]
     InvalidDimensions(usize, usize, usize),
 
+    #[error("Invalid channel: {0}")]
+    InvalidChannel(usize),
+
+    #[error("Invalid number of bands: expected {0}, got {1}")]
+    InvalidBandCount(usize, usize),
+
     #[error("Failed color conversion from {0} to {1}")]
     FailedColorConversion(String, String),
 
+    #[error("Unknown colorspace: {0}")]
+    UnknownColorspace(String),
+
     #[error("Multiple images not supported in image: {0}")]
     MultipleImagesNotSupported(String),
 
     #[error("Invalid data type")]
     InvalidType,
 
+    #[error("Unsupported image format: type={0:?}, channels={1}")]
+    UnsupportedFormat(crate::io::BaseType, usize),
+
+    #[error("Invalid EXIF data: {0}")]
+    InvalidExif(String),
+
+    #[error("Invalid ICC profile: {0}")]
+    InvalidIccProfile(String),
+
     #[cfg(not(feature = "oiio"))]
     #[error("Magick: {0}")]
     Magick(#[from] crate::io::magick::Error),
@@ -1,47 +1,36 @@
 use crate::*;
 
-#[cfg(feature = "parallel")]
-use rayon::prelude::*;
-
-
 /// Filters are used to manipulate images in a generic, composable manner
 pub trait Filter: Sized + Sync {
-    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&Image<impl Type, impl Color>]) -> f64;
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64;
+
+    /// Compute every channel of the output pixel at (x, y). The default calls `compute_at` for
+    /// each channel, filters that share work between channels should override it
+    fn compute_pixel<C: Color>(
+        &self,
+        x: usize,
+        y: usize,
+        input: &[&impl GenericImage],
+        out: &mut Pixel<C>,
+    ) {
+        for (c, f) in out.iter_mut().enumerate() {
+            *f = self.compute_at(x, y, c, input);
+        }
+    }
 
     /// Evaluate a filter on part of an image
     fn eval_partial<A: Type, B: Type, C: Color, D: Color>(
         &self,
         roi: Region,
-        output: &mut Image<A, impl Color>,
-        input: &[&Image<B, impl Color>],
+        output: &mut impl GenericImageMut<T = A>,
+        input: &[&impl GenericImage<T = B>],
     ) {
-        let channels = output.channels();
-
-        #[cfg(feature = "parallel")]
-        let iter = {
-            output.parallel_iter_region_mut(roi)
-        };
-
-        #[cfg(not(feature = "parallel"))]
-        let iter = {
-            output.iter_region_mut(roi)
-        };
-
-        iter.for_each(|((x, y), pixel)| {
-            for (c, px) in pixel.iter_mut().enumerate().take(channels) {
-                px.set_from_norm(self.compute_at(x, y, c, input));
-            }
-        });
+        eval_pixels(self, Some(roi), output, input)
     }
 
     /// Evaluate filter in parallel
-    fn eval(&self, output: &mut Image<impl Type, impl Color>, input: &[&Image<impl Type, impl Color>]) {
-        let channels = output.channels();
-        output.for_each(|(x, y), pixel| {
-            for (c, px) in pixel.iter_mut().enumerate().take(channels) {
-                px.set_from_norm(self.compute_at(x, y, c, input));
-            }
-        });
+    fn eval(&self, output: &mut impl GenericImageMut, input: &[&impl GenericImage]) {
+        eval_pixels(self, None, output, input)
     }
 
     fn join<
@@ -87,6 +76,26 @@ pub trait Filter: Sized + Sync {
     }
 }
 
+/// Evaluate `filter` one pixel at a time using `compute_pixel`, over `roi` of `output` or the
+/// whole image when `roi` is `None`
+fn eval_pixels<F: Filter, O: GenericImageMut, I: GenericImage>(
+    filter: &F,
+    roi: Option<Region>,
+    output: &mut O,
+    input: &[&I],
+) {
+    let f = |(x, y), pixel: &mut [O::T]| {
+        let mut px = Pixel::<O::C>::new();
+        filter.compute_pixel(x, y, input, &mut px);
+        px.copy_to_slice(pixel);
+    };
+
+    match roi {
+        Some(roi) => output.for_each_region(roi, f),
+        None => output.for_each(f),
+    }
+}
+
 /// Executes `a` then `b` and passes the results to `f`
 pub struct Join<
     'a,
@@ -108,9 +117,22 @@ pub struct AndThen<'a, A: 'a + Filter, F: Fn((usize, usize, usize), f64) -> f64>
 impl<'a, A: Filter, F: Sync + Fn((usize, usize, usize), f64) -> f64> Filter
     for AndThen<'a, A, F>
 {
-    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&Image<impl Type, impl Color>]) -> f64 {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
         (self.f)((x, y, c), self.a.compute_at(x, y, c, input))
     }
+
+    fn compute_pixel<C: Color>(
+        &self,
+        x: usize,
+        y: usize,
+        input: &[&impl GenericImage],
+        out: &mut Pixel<C>,
+    ) {
+        self.a.compute_pixel(x, y, input, out);
+        for (c, f) in out.iter_mut().enumerate() {
+            *f = (self.f)((x, y, c), *f);
+        }
+    }
 }
 
 impl<
@@ -120,15 +142,30 @@ impl<
         F: Sync + Fn((usize, usize, usize), f64, f64) -> f64,
     > Filter for Join<'a, A, B, F>
 {
-    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&Image<impl Type, impl Color>]) -> f64 {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
         (&self.f)((x, y, c), self.a.compute_at(x, y, c, input), self.b.compute_at(x, y, c, input))
     }
+
+    fn compute_pixel<C: Color>(
+        &self,
+        x: usize,
+        y: usize,
+        input: &[&impl GenericImage],
+        out: &mut Pixel<C>,
+    ) {
+        let mut b = Pixel::<C>::new();
+        self.a.compute_pixel(x, y, input, out);
+        self.b.compute_pixel(x, y, input, &mut b);
+        for (c, f) in out.iter_mut().enumerate() {
+            *f = (self.f)((x, y, c), *f, b[c]);
+        }
+    }
 }
 
 pub struct Invert;
 
 impl Filter for Invert {
-    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&Image<impl Type, impl Color>]) -> f64 {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
         1.0 - input[0].get_f(x, y, c)
     }
 }
@@ -136,7 +173,7 @@ impl Filter for Invert {
 pub struct Blend;
 
 impl Filter for Blend {
-    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&Image<impl Type, impl Color>]) -> f64 {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
         (input[0].get_f(x, y, c) + input[1].get_f(x, y, c)) / 2.0
     }
 }
@@ -144,11 +181,259 @@ impl Filter for Blend {
 pub struct Gamma(pub f64);
 
 impl Filter for Gamma {
-    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&Image<impl Type, impl Color>]) -> f64 {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
         input[0].get_f(x, y, c).powf(1.0 / self.0)
     }
 }
 
+/// Encode linear light values using a transfer function, alpha is passed through
+///
+/// The output should use a color with the matching `TRANSFER`, for example `Rec2100Pq` for
+/// `TransferFunction::Pq(10000.0)`, so later conversions decode the values
+pub struct Encode(pub TransferFunction);
+
+impl Filter for Encode {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        let f = input[0].get_f(x, y, c);
+        if input[0].meta().alpha_channel() == Some(c) {
+            return f;
+        }
+        self.0.encode(f)
+    }
+}
+
+/// Decode values encoded with a transfer function to linear light, alpha is passed through
+pub struct Decode(pub TransferFunction);
+
+impl Filter for Decode {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        let f = input[0].get_f(x, y, c);
+        if input[0].meta().alpha_channel() == Some(c) {
+            return f;
+        }
+        self.0.decode(f)
+    }
+}
+
+/// BT.2100 HLG system gamma for a display with the given peak luminance in cd/m²
+fn hlg_gamma(peak: f64) -> f64 {
+    1.2 + 0.42 * (peak / 1000.0).log10()
+}
+
+/// Luminance of a BT.2020 pixel after applying `f` to each channel, channels other than the
+/// first 3 are ignored
+fn hlg_luminance(input: &impl GenericImage, x: usize, y: usize, f: impl Fn(f64) -> f64) -> f64 {
+    0.2627 * f(input.get_f(x, y, 0))
+        + 0.6780 * f(input.get_f(x, y, 1))
+        + 0.0593 * f(input.get_f(x, y, 2))
+}
+
+/// BT.2100 HLG EOTF: converts an HLG signal with BT.2020 primaries to display linear light,
+/// normalized so that 1.0 is the peak luminance of the display in cd/m²
+pub struct HlgEotf(pub f64);
+
+impl Filter for HlgEotf {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        let f = input[0].get_f(x, y, c);
+        if c >= 3 || input[0].meta().alpha_channel() == Some(c) {
+            return f;
+        }
+
+        let hlg = TransferFunction::Hlg;
+        let luminance = hlg_luminance(input[0], x, y, |f| hlg.decode(f));
+        luminance.powf(hlg_gamma(self.0) - 1.0) * hlg.decode(f)
+    }
+}
+
+/// Inverse of `HlgEotf`: converts display linear light with BT.2020 primaries, normalized to the
+/// peak luminance of the display in cd/m², to an HLG signal
+pub struct HlgInverseEotf(pub f64);
+
+impl Filter for HlgInverseEotf {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        let f = input[0].get_f(x, y, c);
+        if c >= 3 || input[0].meta().alpha_channel() == Some(c) {
+            return f;
+        }
+
+        let gamma = hlg_gamma(self.0);
+        let luminance = hlg_luminance(input[0], x, y, |f| f);
+        let scene = if luminance > 0.0 {
+            f * luminance.powf((1.0 - gamma) / gamma)
+        } else {
+            0.0
+        };
+        TransferFunction::Hlg.encode(scene)
+    }
+}
+
+/// Map each channel through a lookup table indexed by histogram bin, alpha and values outside of
+/// `0..=1` are passed through. Tables only contain values in `0..=1`, so the order of values is
+/// preserved
+fn lookup(tables: &[Vec<f64>], x: usize, y: usize, c: usize, input: &impl GenericImage) -> f64 {
+    let f = input.get_f(x, y, c);
+    let table = match tables.get(c) {
+        Some(table) if !table.is_empty() && input.meta().alpha_channel() != Some(c) => table,
+        _ => return f,
+    };
+    if !(0.0..=1.0).contains(&f) {
+        return f;
+    }
+    let index = (f * (table.len() - 1) as f64).round() as usize;
+    table[index]
+}
+
+/// Global histogram equalization, spreads the values of each channel so their cumulative
+/// distribution becomes linear. Values outside of `0..=1`, as found in floating point images, are
+/// left unchanged
+pub struct Equalize {
+    tables: Vec<Vec<f64>>,
+}
+
+impl Equalize {
+    /// Create a filter from the per-channel histograms of the input image, see `Image::histogram`
+    pub fn new(histograms: &[Histogram]) -> Equalize {
+        let tables = histograms
+            .iter()
+            .map(|hist| {
+                let cdf = hist.cumulative();
+                let min = cdf.iter().copied().find(|x| *x > 0.0).unwrap_or(0.0);
+                if min >= 1.0 {
+                    // Every value is in the same bin, leave the channel as is
+                    return (0..hist.len()).map(|i| hist.bin_value(i)).collect();
+                }
+                cdf.iter().map(|x| ((x - min) / (1.0 - min)).max(0.0)).collect()
+            })
+            .collect();
+        Equalize { tables }
+    }
+}
+
+impl Filter for Equalize {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        lookup(&self.tables, x, y, c, input[0])
+    }
+}
+
+/// Histogram matching, maps the values of each channel so their histogram matches a reference
+/// histogram. Values outside of `0..=1` are left unchanged
+pub struct MatchHistogram {
+    tables: Vec<Vec<f64>>,
+}
+
+impl MatchHistogram {
+    /// Create a filter from the per-channel histograms of the input image and of the reference
+    /// image, see `Image::histogram`
+    pub fn new(input: &[Histogram], reference: &[Histogram]) -> MatchHistogram {
+        let tables = input
+            .iter()
+            .zip(reference)
+            .map(|(hist, reference)| {
+                hist.cumulative()
+                    .iter()
+                    .map(|x| reference.quantile(*x))
+                    .collect()
+            })
+            .collect();
+        MatchHistogram { tables }
+    }
+}
+
+impl Filter for MatchHistogram {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        lookup(&self.tables, x, y, c, input[0])
+    }
+}
+
+/// Number of histogram bins used by `Clahe`
+const CLAHE_BINS: usize = 256;
+
+/// Contrast-limited adaptive histogram equalization of the first channel, other channels are
+/// passed through. See `Image::clahe` to equalize the lightness of color images
+///
+/// The image is split into a grid of tiles which are equalized separately, results are
+/// interpolated bilinearly between the centers of neighboring tiles
+pub struct Clahe {
+    tiles: (usize, usize),
+    size: (f64, f64),
+    tables: Vec<Vec<f64>>,
+}
+
+impl Clahe {
+    /// Create a filter for `image` using a grid of `tiles` (horizontal, vertical). Histogram bins
+    /// are limited to `clip_limit` times the average bin count of a tile before equalizing, a
+    /// limit of `0.0` or less disables clipping
+    pub fn new<T: Type, C: Color>(
+        image: &Image<T, C>,
+        tiles: (usize, usize),
+        clip_limit: f64,
+    ) -> Clahe {
+        let (width, height) = (image.width(), image.height());
+
+        // Empty images have no tiles to measure, a single identity table keeps lookups valid
+        if width == 0 || height == 0 {
+            let identity = (0..CLAHE_BINS)
+                .map(|i| i as f64 / (CLAHE_BINS - 1) as f64)
+                .collect();
+            return Clahe {
+                tiles: (1, 1),
+                size: (1.0, 1.0),
+                tables: vec![identity],
+            };
+        }
+
+        let tiles = (tiles.0.clamp(1, width), tiles.1.clamp(1, height));
+
+        let mut tables = Vec::with_capacity(tiles.0 * tiles.1);
+        for j in 0..tiles.1 {
+            for i in 0..tiles.0 {
+                let (x0, x1) = (i * width / tiles.0, (i + 1) * width / tiles.0);
+                let (y0, y1) = (j * height / tiles.1, (j + 1) * height / tiles.1);
+                let roi = Region::new(x0, y0, x1 - x0, y1 - y0);
+                let mut hist = image
+                    .reduce_region(roi, &reduce::ComputeHistogram { bins: CLAHE_BINS })
+                    .swap_remove(0);
+
+                if clip_limit > 0.0 {
+                    let average = (roi.width * roi.height) as f64 / CLAHE_BINS as f64;
+                    hist.clip((clip_limit * average).ceil().max(1.0) as usize);
+                }
+                tables.push(hist.cumulative());
+            }
+        }
+
+        Clahe {
+            tiles,
+            size: (width as f64 / tiles.0 as f64, height as f64 / tiles.1 as f64),
+            tables,
+        }
+    }
+}
+
+/// Get the indices of the two closest tile centers and the weight of the second one
+fn clahe_neighbors(pos: usize, size: f64, count: usize) -> (usize, usize, f64) {
+    let t = ((pos as f64 + 0.5) / size - 0.5).clamp(0.0, (count - 1) as f64);
+    let a = t.floor() as usize;
+    (a, (a + 1).min(count - 1), t - a as f64)
+}
+
+impl Filter for Clahe {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        let f = input[0].get_f(x, y, c);
+        if c != 0 {
+            return f;
+        }
+
+        let index = (f.clamp(0.0, 1.0) * (CLAHE_BINS - 1) as f64).round() as usize;
+        let (x0, x1, fx) = clahe_neighbors(x, self.size.0, self.tiles.0);
+        let (y0, y1, fy) = clahe_neighbors(y, self.size.1, self.tiles.1);
+        let at = |i: usize, j: usize| self.tables[j * self.tiles.0 + i][index];
+        let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+        let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
 pub enum AsyncMode {
     Pixel,
     Row,
@@ -188,31 +473,23 @@ impl<'a, F: Unpin + Filter, T: Type, C: Color, U: Unpin + Type,  D: Unpin + Colo
     ) -> std::task::Poll<Self::Output> {
         let input = &self.input[0];
         let filter = std::pin::Pin::get_mut(self);
-
+        let mut px = Pixel::<D>::new();
 
         match filter.mode {
             AsyncMode::Row => {
                 for i in 0 .. input.width() {
-                    for c in 0..C::CHANNELS {
-                        let f = filter
-                            .filter
-                            .compute_at(i, filter.y, c, &filter.input);
-                        filter.output.set_f(i, filter.y, c, f);
-                    }
+                    filter.filter.compute_pixel(i, filter.y, filter.input, &mut px);
+                    filter.output.set_pixel(i, filter.y, &px);
                 }
                 filter.y += 1;
             }
             AsyncMode::Pixel => {
-                for c in 0..C::CHANNELS {
-                    let f = filter
-                        .filter
-                        .compute_at(filter.x, filter.y, c, &filter.input);
-                    filter.output.set_f(filter.x, filter.y, c, f);
-                    filter.x += 1;
-                    if filter.x >= input.width() {
-                        filter.x = 0;
-                        filter.y += 1;
-                    }
+                filter.filter.compute_pixel(filter.x, filter.y, filter.input, &mut px);
+                filter.output.set_pixel(filter.x, filter.y, &px);
+                filter.x += 1;
+                if filter.x >= input.width() {
+                    filter.x = 0;
+                    filter.y += 1;
                 }
             }
         }
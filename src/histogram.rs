@@ -48,9 +48,9 @@ impl Histogram {
         hist
     }
 
+    /// Count a value, values outside of `0..=1` are counted in the first or last bin
     pub fn add_value<T: Type>(&mut self, value: T) {
-        let x = value.to_norm() * (self.bins.len() - 1) as f64;
-        self.incr_bin(x.round() as usize)
+        self.incr_bin(self.bin_index(value.to_norm()))
     }
 
     pub fn incr_bin(&mut self, index: usize) {
@@ -116,6 +116,78 @@ impl Histogram {
     pub fn sum(&self) -> usize {
         self.total
     }
+
+    /// Normalized value represented by a bin
+    pub fn bin_value(&self, index: usize) -> f64 {
+        if self.len() < 2 {
+            return 0.0;
+        }
+
+        index as f64 / (self.len() - 1) as f64
+    }
+
+    /// Get the index of the bin a normalized value is counted in
+    pub fn bin_index(&self, value: f64) -> usize {
+        let x = value.clamp(0.0, 1.0) * (self.len().max(1) - 1) as f64;
+        x.round() as usize
+    }
+
+    /// Limit the count of each bin to `limit`, redistributing the excess evenly across all bins
+    /// so the total count is unchanged
+    pub fn clip(&mut self, limit: usize) {
+        let mut excess = 0;
+        for bin in self.bins.iter_mut() {
+            excess += bin.saturating_sub(limit);
+            *bin = (*bin).min(limit);
+        }
+
+        let n = self.bins.len();
+        if n == 0 || excess == 0 {
+            return;
+        }
+
+        let (each, remainder) = (excess / n, excess % n);
+        for bin in self.bins.iter_mut() {
+            *bin += each;
+        }
+
+        if let Some(step) = n.checked_div(remainder) {
+            for bin in self.bins.iter_mut().step_by(step).take(remainder) {
+                *bin += 1;
+            }
+        }
+    }
+
+    /// Cumulative distribution, the fraction of values counted in each bin or any bin before it
+    pub fn cumulative(&self) -> Vec<f64> {
+        let total = self.total.max(1) as f64;
+        let mut sum = 0;
+        self.bins
+            .iter()
+            .map(|x| {
+                sum += x;
+                sum as f64 / total
+            })
+            .collect()
+    }
+
+    /// Get the normalized value of the first bin where the cumulative distribution reaches `p`
+    /// percent, `p` is clamped to `0..=100`
+    pub fn percentile(&self, p: f64) -> f64 {
+        self.quantile(p / 100.0)
+    }
+
+    /// Get the normalized value of the first bin where the cumulative distribution reaches `q`,
+    /// `q` is clamped to `0..=1`
+    pub fn quantile(&self, q: f64) -> f64 {
+        let q = q.clamp(0.0, 1.0);
+        let index = self
+            .cumulative()
+            .iter()
+            .position(|x| *x > 0.0 && *x >= q)
+            .unwrap_or(0);
+        self.bin_value(index)
+    }
 }
 
 #[cfg(test)]
@@ -135,4 +207,33 @@ mod tests {
             assert!(h.distribution().into_iter().skip(1).sum::<f64>() == 0.0);
         }
     }
+
+    #[test]
+    fn test_histogram_cumulative() {
+        let mut hist = Histogram::new(5);
+        for x in [0.25f32, 0.25, 0.5, 1.0] {
+            hist.add_value(x);
+        }
+
+        assert_eq!(hist.cumulative(), [0.0, 0.5, 0.75, 0.75, 1.0]);
+        assert_eq!(hist.bin_index(0.3), 1);
+        assert_eq!(hist.bin_value(2), 0.5);
+        assert_eq!(hist.percentile(0.0), 0.25);
+        assert_eq!(hist.percentile(50.0), 0.25);
+        assert_eq!(hist.percentile(60.0), 0.5);
+        assert_eq!(hist.percentile(100.0), 1.0);
+        assert_eq!(Histogram::new(5).percentile(50.0), 0.0);
+    }
+
+    #[test]
+    fn test_histogram_clip() {
+        let mut hist = Histogram::new(4);
+        hist[0] = 10;
+        hist[2] = 3;
+        hist.total = 13;
+
+        hist.clip(4);
+        assert_eq!(hist.as_ref(), &[6, 1, 5, 1]);
+        assert_eq!(hist.as_ref().iter().sum::<usize>(), hist.sum());
+    }
 }
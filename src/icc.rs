@@ -0,0 +1,1118 @@
+//! ICC color profiles
+//!
+//! Supports ICC v2 and v4 matrix/TRC, gray TRC and LUT-based (`lut8`, `lut16`, `lutAtoB` and
+//! `lutBtoA`) profiles. Conversions between profiles go through the XYZ profile connection
+//! space, see `Transform`.
+
+use std::path::Path;
+
+use crate::*;
+
+/// D50 illuminant, the white point of the profile connection space
+pub const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+/// 3x3 matrix, stored row-major
+pub type Matrix = [[f64; 3]; 3];
+
+/// sRGB colorants adapted to D50, as found in the sRGB IEC61966-2.1 profile
+const SRGB_D50: Matrix = [
+    [0.4360747, 0.3850649, 0.1430804],
+    [0.2225045, 0.7168786, 0.0606169],
+    [0.0139322, 0.0971045, 0.7141733],
+];
+
+fn err(s: &str) -> Error {
+    Error::InvalidIccProfile(s.into())
+}
+
+fn u16be(d: &[u8], offset: usize) -> Result<u16, Error> {
+    match d.get(offset..offset + 2) {
+        Some(b) => Ok(u16::from_be_bytes([b[0], b[1]])),
+        None => Err(err("unexpected end of data")),
+    }
+}
+
+fn u32be(d: &[u8], offset: usize) -> Result<u32, Error> {
+    match d.get(offset..offset + 4) {
+        Some(b) => Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(err("unexpected end of data")),
+    }
+}
+
+fn u8at(d: &[u8], offset: usize) -> Result<u8, Error> {
+    d.get(offset)
+        .copied()
+        .ok_or_else(|| err("unexpected end of data"))
+}
+
+fn s15f16(d: &[u8], offset: usize) -> Result<f64, Error> {
+    Ok(u32be(d, offset)? as i32 as f64 / 65536.0)
+}
+
+fn signature(d: &[u8], offset: usize) -> Result<[u8; 4], Error> {
+    match d.get(offset..offset + 4) {
+        Some(b) => Ok([b[0], b[1], b[2], b[3]]),
+        None => Err(err("unexpected end of data")),
+    }
+}
+
+pub(crate) fn mul(m: &Matrix, v: [f64; 3]) -> [f64; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+/// Invert a 3x3 matrix, returns `None` if the matrix is singular
+pub fn invert(m: &Matrix) -> Option<Matrix> {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+
+    let d = 1.0 / det;
+    Some([
+        [
+            (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * d,
+            (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * d,
+            (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * d,
+        ],
+        [
+            (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * d,
+            (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * d,
+            (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * d,
+        ],
+        [
+            (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * d,
+            (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * d,
+            (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * d,
+        ],
+    ])
+}
+
+pub(crate) const EPSILON: f64 = 216.0 / 24389.0;
+pub(crate) const KAPPA: f64 = 24389.0 / 27.0;
+
+/// Convert CIE Lab to XYZ relative to the given reference white
+pub(crate) fn lab_to_xyz(lab: [f64; 3], white: &[f64; 3]) -> [f64; 3] {
+    let fy = (lab[0] + 16.0) / 116.0;
+    let fx = fy + lab[1] / 500.0;
+    let fz = fy - lab[2] / 200.0;
+    let f = |t: f64| {
+        let t3 = t * t * t;
+        if t3 > EPSILON {
+            t3
+        } else {
+            (116.0 * t - 16.0) / KAPPA
+        }
+    };
+    [f(fx) * white[0], f(fy) * white[1], f(fz) * white[2]]
+}
+
+/// Convert XYZ to CIE Lab relative to the given reference white
+pub(crate) fn xyz_to_lab(xyz: [f64; 3], white: &[f64; 3]) -> [f64; 3] {
+    let f = |t: f64| {
+        if t > EPSILON {
+            t.cbrt()
+        } else {
+            (KAPPA * t + 16.0) / 116.0
+        }
+    };
+    let fx = f(xyz[0] / white[0]);
+    let fy = f(xyz[1] / white[1]);
+    let fz = f(xyz[2] / white[2]);
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Tone reproduction curve
+#[derive(Debug, Clone, PartialEq)]
+pub enum Curve {
+    Identity,
+    Gamma(f64),
+
+    /// Evenly spaced samples in the range `0..=1`
+    Table(Vec<f64>),
+
+    /// ICC `parametricCurveType` function type and its parameters: `g, a, b, c, d, e, f`
+    Parametric(u16, [f64; 7]),
+}
+
+impl Curve {
+    /// The sRGB transfer function
+    pub fn srgb() -> Curve {
+        Curve::Parametric(
+            3,
+            [
+                2.4,
+                1.0 / 1.055,
+                0.055 / 1.055,
+                1.0 / 12.92,
+                0.04045,
+                0.0,
+                0.0,
+            ],
+        )
+    }
+
+    /// Evaluate the curve
+    pub fn eval(&self, x: f64) -> f64 {
+        match self {
+            Curve::Identity => x,
+            Curve::Gamma(g) => x.max(0.0).powf(*g),
+            Curve::Table(t) => {
+                if t.is_empty() {
+                    return x;
+                } else if t.len() == 1 {
+                    return t[0];
+                }
+
+                let pos = x.clamp(0.0, 1.0) * (t.len() - 1) as f64;
+                let i = pos.floor() as usize;
+                if i >= t.len() - 1 {
+                    return t[t.len() - 1];
+                }
+                t[i] + (t[i + 1] - t[i]) * (pos - i as f64)
+            }
+            Curve::Parametric(kind, p) => {
+                let [g, a, b, c, d, e, f] = *p;
+                let pow = |x: f64| (a * x + b).max(0.0).powf(g);
+                match kind {
+                    0 => x.max(0.0).powf(g),
+                    1 if x >= -b / a => pow(x),
+                    1 => 0.0,
+                    2 if x >= -b / a => pow(x) + c,
+                    2 => c,
+                    3 if x >= d => pow(x),
+                    3 => c * x,
+                    4 if x >= d => pow(x) + e,
+                    4 => c * x + f,
+                    _ => x,
+                }
+            }
+        }
+    }
+
+    /// Evaluate the inverse of the curve, the curve is expected to be monotonic
+    pub fn inverse(&self, y: f64) -> f64 {
+        match self {
+            Curve::Identity => y,
+            Curve::Gamma(g) => y.max(0.0).powf(1.0 / g),
+            _ => {
+                let increasing = self.eval(1.0) >= self.eval(0.0);
+                let (mut lo, mut hi) = (0.0, 1.0);
+                for _ in 0..48 {
+                    let mid = (lo + hi) / 2.0;
+                    if (self.eval(mid) < y) == increasing {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                (lo + hi) / 2.0
+            }
+        }
+    }
+
+    /// Parse a `curv` or `para` element, returns the curve and the size of the element in bytes
+    fn parse(d: &[u8]) -> Result<(Curve, usize), Error> {
+        match &signature(d, 0)? {
+            b"curv" => {
+                let n = u32be(d, 8)? as usize;
+                let curve = match n {
+                    0 => Curve::Identity,
+                    1 => Curve::Gamma(u16be(d, 12)? as f64 / 256.0),
+                    _ => Curve::Table(
+                        (0..n)
+                            .map(|i| u16be(d, 12 + i * 2).map(|x| x as f64 / 65535.0))
+                            .collect::<Result<_, _>>()?,
+                    ),
+                };
+                Ok((curve, 12 + n * 2))
+            }
+            b"para" => {
+                let kind = u16be(d, 8)?;
+                let n = match kind {
+                    0 => 1,
+                    1 => 3,
+                    2 => 4,
+                    3 => 5,
+                    4 => 7,
+                    _ => return Err(err("unknown parametric curve type")),
+                };
+                let mut params = [0.0; 7];
+                for (i, p) in params.iter_mut().enumerate().take(n) {
+                    *p = s15f16(d, 12 + i * 4)?;
+                }
+                Ok((Curve::Parametric(kind, params), 12 + n * 4))
+            }
+            _ => Err(err("unknown curve type")),
+        }
+    }
+
+    /// Parse `n` curves stored one after another, each aligned to 4 bytes
+    fn parse_sequence(d: &[u8], n: usize) -> Result<Vec<Curve>, Error> {
+        let mut offset = 0;
+        let mut curves = Vec::with_capacity(n);
+        for _ in 0..n {
+            let (curve, len) = Curve::parse(d.get(offset..).unwrap_or_default())?;
+            curves.push(curve);
+            offset += (len + 3) & !3;
+        }
+        Ok(curves)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut d = Vec::new();
+        match self {
+            Curve::Identity => {
+                d.extend_from_slice(b"curv\0\0\0\0");
+                d.extend_from_slice(&0u32.to_be_bytes());
+            }
+            Curve::Gamma(g) => {
+                d.extend_from_slice(b"curv\0\0\0\0");
+                d.extend_from_slice(&1u32.to_be_bytes());
+                d.extend_from_slice(&((g * 256.0).round() as u16).to_be_bytes());
+            }
+            Curve::Table(t) => {
+                d.extend_from_slice(b"curv\0\0\0\0");
+                d.extend_from_slice(&(t.len() as u32).to_be_bytes());
+                for x in t {
+                    d.extend_from_slice(&((x * 65535.0).round() as u16).to_be_bytes());
+                }
+            }
+            Curve::Parametric(kind, p) => {
+                d.extend_from_slice(b"para\0\0\0\0");
+                d.extend_from_slice(&kind.to_be_bytes());
+                d.extend_from_slice(&[0, 0]);
+                let n = match kind {
+                    0 => 1,
+                    1 => 3,
+                    2 => 4,
+                    3 => 5,
+                    _ => 7,
+                };
+                for x in p.iter().take(n) {
+                    d.extend_from_slice(&((x * 65536.0).round() as i32).to_be_bytes());
+                }
+            }
+        }
+        d
+    }
+}
+
+/// Maximum number of lookup table inputs allowed by the ICC specification
+const MAX_CLUT_INPUTS: usize = 15;
+
+/// Multi-dimensional color lookup table
+#[derive(Debug, Clone, PartialEq)]
+struct Clut {
+    grid: Vec<usize>,
+    outputs: usize,
+    data: Vec<f64>,
+}
+
+impl Clut {
+    fn parse(d: &[u8], grid: Vec<usize>, outputs: usize, precision: usize) -> Result<Clut, Error> {
+        if grid.is_empty() || grid.len() > MAX_CLUT_INPUTS {
+            return Err(err("invalid number of lookup table inputs"));
+        }
+        if grid.iter().any(|g| *g < 2) {
+            return Err(err("invalid lookup table grid size"));
+        }
+
+        let size = if precision == 1 { 1 } else { 2 };
+        let n = grid
+            .iter()
+            .try_fold(outputs, |n, g| n.checked_mul(*g))
+            .filter(|n| n.checked_mul(size).is_some_and(|len| len <= d.len()))
+            .ok_or_else(|| err("lookup table too large"))?;
+        let data = (0..n)
+            .map(|i| match precision {
+                1 => u8at(d, i).map(|x| x as f64 / 255.0),
+                _ => u16be(d, i * 2).map(|x| x as f64 / 65535.0),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Clut {
+            grid,
+            outputs,
+            data,
+        })
+    }
+
+    /// Multilinear interpolation, the first input channel varies slowest
+    fn eval(&self, input: &[f64]) -> Vec<f64> {
+        let n = self.grid.len();
+        let mut base = vec![0; n];
+        let mut frac = vec![0.0; n];
+        for i in 0..n {
+            let g = self.grid[i];
+            let pos = input[i].clamp(0.0, 1.0) * (g - 1) as f64;
+            base[i] = (pos.floor() as usize).min(g - 2);
+            frac[i] = pos - base[i] as f64;
+        }
+
+        let mut out = vec![0.0; self.outputs];
+        for corner in 0..(1usize << n) {
+            let mut weight = 1.0;
+            let mut index = 0;
+            for i in 0..n {
+                let bit = (corner >> (n - 1 - i)) & 1;
+                weight *= if bit == 1 { frac[i] } else { 1.0 - frac[i] };
+                index = index * self.grid[i] + base[i] + bit;
+            }
+
+            if weight == 0.0 {
+                continue;
+            }
+
+            for (o, x) in out.iter_mut().enumerate() {
+                *x += weight * self.data[index * self.outputs + o];
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stage {
+    Curves(Vec<Curve>),
+
+    /// 3x3 matrix, stored row-major, followed by an offset
+    Matrix([f64; 12]),
+    Clut(Clut),
+}
+
+impl Stage {
+    fn eval(&self, input: Vec<f64>) -> Vec<f64> {
+        match self {
+            Stage::Curves(curves) => input
+                .iter()
+                .zip(curves.iter())
+                .map(|(x, c)| c.eval(*x))
+                .collect(),
+            Stage::Matrix(m) => (0..3)
+                .map(|i| {
+                    m[i * 3] * input[0]
+                        + m[i * 3 + 1] * input[1]
+                        + m[i * 3 + 2] * input[2]
+                        + m[9 + i]
+                })
+                .collect(),
+            Stage::Clut(clut) => clut.eval(&input),
+        }
+    }
+}
+
+/// A `lut8`, `lut16`, `lutAtoB` or `lutBtoA` pipeline
+#[derive(Debug, Clone, PartialEq)]
+struct Lut {
+    stages: Vec<Stage>,
+
+    /// `lut16` elements use the legacy 16-bit Lab encoding
+    legacy_lab: bool,
+}
+
+impl Lut {
+    /// Parse a LUT element, the matrix in `lut8` and `lut16` elements is only used when the input
+    /// is XYZ
+    fn parse(d: &[u8], xyz_input: bool) -> Result<Lut, Error> {
+        let inputs = u8at(d, 8)? as usize;
+        let outputs = u8at(d, 9)? as usize;
+        let kind = signature(d, 0)?;
+
+        let matrix = |offset: usize, with_offset: bool| -> Result<Stage, Error> {
+            let mut m = [0.0; 12];
+            let n = if with_offset { 12 } else { 9 };
+            for (i, x) in m.iter_mut().enumerate().take(n) {
+                *x = s15f16(d, offset + i * 4)?;
+            }
+            Ok(Stage::Matrix(m))
+        };
+
+        match &kind {
+            b"mft1" | b"mft2" => {
+                let grid = u8at(d, 10)? as usize;
+                let (precision, in_entries, out_entries, start) = if &kind == b"mft1" {
+                    (1, 256, 256, 48)
+                } else {
+                    (2, u16be(d, 48)? as usize, u16be(d, 50)? as usize, 52)
+                };
+
+                let table = |offset: usize, n: usize| -> Result<Curve, Error> {
+                    let t = (0..n)
+                        .map(|i| match precision {
+                            1 => u8at(d, offset + i).map(|x| x as f64 / 255.0),
+                            _ => u16be(d, offset + i * 2).map(|x| x as f64 / 65535.0),
+                        })
+                        .collect::<Result<_, _>>()?;
+                    Ok(Curve::Table(t))
+                };
+
+                let mut stages = Vec::new();
+                if xyz_input && inputs == 3 {
+                    stages.push(matrix(12, false)?);
+                }
+
+                let mut offset = start;
+                let input = (0..inputs)
+                    .map(|i| table(offset + i * in_entries * precision, in_entries))
+                    .collect::<Result<_, _>>()?;
+                stages.push(Stage::Curves(input));
+                offset += inputs * in_entries * precision;
+
+                let clut = Clut::parse(
+                    d.get(offset..).unwrap_or_default(),
+                    vec![grid; inputs],
+                    outputs,
+                    precision,
+                )?;
+                offset += clut.data.len() * precision;
+                stages.push(Stage::Clut(clut));
+
+                let output = (0..outputs)
+                    .map(|i| table(offset + i * out_entries * precision, out_entries))
+                    .collect::<Result<_, _>>()?;
+                stages.push(Stage::Curves(output));
+
+                Ok(Lut {
+                    stages,
+                    legacy_lab: &kind == b"mft2",
+                })
+            }
+            b"mAB " | b"mBA " => {
+                let a_to_b = &kind == b"mAB ";
+                let element = |n: usize| -> Result<Option<usize>, Error> {
+                    let offset = u32be(d, 12 + n * 4)? as usize;
+                    Ok(if offset == 0 { None } else { Some(offset) })
+                };
+                let curves = |offset: Option<usize>, n: usize| -> Result<Option<Stage>, Error> {
+                    match offset {
+                        Some(offset) => Ok(Some(Stage::Curves(Curve::parse_sequence(
+                            d.get(offset..).unwrap_or_default(),
+                            n,
+                        )?))),
+                        None => Ok(None),
+                    }
+                };
+
+                let b = curves(element(0)?, if a_to_b { outputs } else { inputs })?;
+                let m = match element(1)? {
+                    Some(offset) => Some(matrix(offset, true)?),
+                    None => None,
+                };
+                let mc = curves(element(2)?, 3)?;
+                let clut = match element(3)? {
+                    Some(offset) => {
+                        let grid = (0..inputs)
+                            .map(|i| u8at(d, offset + i).map(|x| x as usize))
+                            .collect::<Result<_, _>>()?;
+                        let precision = u8at(d, offset + 16)? as usize;
+                        Some(Stage::Clut(Clut::parse(
+                            d.get(offset + 20..).unwrap_or_default(),
+                            grid,
+                            outputs,
+                            precision,
+                        )?))
+                    }
+                    None => None,
+                };
+                let a = curves(element(4)?, if a_to_b { inputs } else { outputs })?;
+
+                let stages = if a_to_b {
+                    vec![a, clut, mc, m, b]
+                } else {
+                    vec![b, m, mc, clut, a]
+                };
+
+                Ok(Lut {
+                    stages: stages.into_iter().flatten().collect(),
+                    legacy_lab: false,
+                })
+            }
+            _ => Err(err("unknown LUT type")),
+        }
+    }
+
+    fn eval(&self, input: &[f64]) -> Vec<f64> {
+        self.stages
+            .iter()
+            .fold(input.to_vec(), |acc, stage| stage.eval(acc))
+    }
+
+    /// Convert from the encoded PCS values used by the LUT to XYZ
+    fn decode_pcs(&self, v: &[f64], lab: bool) -> [f64; 3] {
+        if lab {
+            let s = if self.legacy_lab {
+                65535.0 / 65280.0
+            } else {
+                1.0
+            };
+            lab_to_xyz(
+                [
+                    v[0] * s * 100.0,
+                    v[1] * s * 255.0 - 128.0,
+                    v[2] * s * 255.0 - 128.0,
+                ],
+                &D50,
+            )
+        } else {
+            let s = 65535.0 / 32768.0;
+            [v[0] * s, v[1] * s, v[2] * s]
+        }
+    }
+
+    /// Convert from XYZ to the encoded PCS values used by the LUT
+    fn encode_pcs(&self, xyz: [f64; 3], lab: bool) -> [f64; 3] {
+        let v = if lab {
+            let s = if self.legacy_lab {
+                65280.0 / 65535.0
+            } else {
+                1.0
+            };
+            let lab = xyz_to_lab(xyz, &D50);
+            [
+                lab[0] / 100.0 * s,
+                (lab[1] + 128.0) / 255.0 * s,
+                (lab[2] + 128.0) / 255.0 * s,
+            ]
+        } else {
+            let s = 32768.0 / 65535.0;
+            [xyz[0] * s, xyz[1] * s, xyz[2] * s]
+        };
+        [
+            v[0].clamp(0.0, 1.0),
+            v[1].clamp(0.0, 1.0),
+            v[2].clamp(0.0, 1.0),
+        ]
+    }
+}
+
+/// Parsed ICC profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    data: Vec<u8>,
+
+    /// Major and minor version
+    pub version: (u8, u8),
+
+    /// Profile class, for example `mntr` or `prtr`
+    pub class: [u8; 4],
+
+    /// Data color space, for example `RGB ` or `CMYK`
+    pub color_space: [u8; 4],
+
+    /// Profile connection space, `XYZ ` or `Lab `
+    pub pcs: [u8; 4],
+    pub description: Option<String>,
+
+    /// Media white point
+    pub white_point: [f64; 3],
+    matrix: Option<Matrix>,
+    inverse_matrix: Option<Matrix>,
+    trc: Vec<Curve>,
+    a2b: Option<Lut>,
+    b2a: Option<Lut>,
+}
+
+/// Returns true if `data` starts with an ICC profile header
+pub fn is_profile(data: &[u8]) -> bool {
+    data.len() >= 128 && &data[36..40] == b"acsp"
+}
+
+impl Profile {
+    /// Parse an ICC profile
+    pub fn parse(data: impl Into<Vec<u8>>) -> Result<Profile, Error> {
+        let data = data.into();
+        if !is_profile(&data) {
+            return Err(err("missing profile header"));
+        }
+
+        let mut profile = Profile {
+            version: (data[8], data[9] >> 4),
+            class: signature(&data, 12)?,
+            color_space: signature(&data, 16)?,
+            pcs: signature(&data, 20)?,
+            description: None,
+            white_point: D50,
+            matrix: None,
+            inverse_matrix: None,
+            trc: Vec::new(),
+            a2b: None,
+            b2a: None,
+            data: Vec::new(),
+        };
+
+        if &profile.pcs != b"XYZ " && &profile.pcs != b"Lab " {
+            return Err(err("unsupported profile connection space"));
+        }
+
+        let tag = |sig: &[u8; 4]| -> Result<Option<&[u8]>, Error> {
+            let count = u32be(&data, 128)? as usize;
+            for i in 0..count {
+                let entry = 132 + i * 12;
+                if &signature(&data, entry)? == sig {
+                    let offset = u32be(&data, entry + 4)? as usize;
+                    let size = u32be(&data, entry + 8)? as usize;
+                    return match data.get(offset..offset.saturating_add(size)) {
+                        Some(d) => Ok(Some(d)),
+                        None => Err(err("tag out of bounds")),
+                    };
+                }
+            }
+            Ok(None)
+        };
+
+        let xyz = |d: &[u8]| -> Result<[f64; 3], Error> {
+            Ok([s15f16(d, 8)?, s15f16(d, 12)?, s15f16(d, 16)?])
+        };
+
+        if let Some(d) = tag(b"desc")? {
+            profile.description = parse_text(d).ok();
+        }
+
+        if let Some(d) = tag(b"wtpt")? {
+            profile.white_point = xyz(d)?;
+        }
+
+        if let (Some(r), Some(g), Some(b)) = (tag(b"rXYZ")?, tag(b"gXYZ")?, tag(b"bXYZ")?) {
+            let (r, g, b) = (xyz(r)?, xyz(g)?, xyz(b)?);
+            let m = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+            profile.inverse_matrix = invert(&m);
+            profile.matrix = Some(m);
+        }
+
+        if let (Some(r), Some(g), Some(b)) = (tag(b"rTRC")?, tag(b"gTRC")?, tag(b"bTRC")?) {
+            profile.trc = vec![Curve::parse(r)?.0, Curve::parse(g)?.0, Curve::parse(b)?.0];
+        } else if let Some(k) = tag(b"kTRC")? {
+            profile.trc = vec![Curve::parse(k)?.0];
+        }
+
+        let xyz_pcs = &profile.pcs == b"XYZ ";
+        if let Some(d) = tag(b"A2B0")? {
+            profile.a2b = Some(Lut::parse(d, false)?);
+        }
+
+        if let Some(d) = tag(b"B2A0")? {
+            profile.b2a = Some(Lut::parse(d, xyz_pcs)?);
+        }
+
+        let has_matrix_trc = profile.matrix.is_some() && profile.trc.len() == 3;
+        let has_gray_trc = profile.trc.len() == 1;
+        if profile.a2b.is_none() && !has_matrix_trc && !has_gray_trc {
+            return Err(err("profile has no supported transform"));
+        }
+
+        profile.data = data;
+        Ok(profile)
+    }
+
+    /// Read an ICC profile from a `.icc` file or from the metadata of a JPEG, TIFF, PNG or WebP
+    /// image
+    pub fn read(path: impl AsRef<Path>) -> Result<Option<Profile>, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .map_err(|_| Error::UnableToOpenImage(path.to_string_lossy().to_string()))?;
+
+        if is_profile(&data) {
+            return Profile::parse(data).map(Some);
+        }
+
+        match io::container::icc_profile(&data)? {
+            Some(data) => Profile::parse(data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Create an RGB matrix/TRC profile from D50-adapted colorants and a transfer curve
+    pub fn matrix_trc(description: &str, colorants: Matrix, curve: Curve) -> Profile {
+        let m = colorants;
+        let xyz = |i: usize| xyz_bytes([m[0][i], m[1][i], m[2][i]]);
+        let trc = curve.to_bytes();
+        let data = build(
+            b"RGB ",
+            &[
+                (b"desc", text_bytes(description)),
+                (b"wtpt", xyz_bytes(D50)),
+                (b"rXYZ", xyz(0)),
+                (b"gXYZ", xyz(1)),
+                (b"bXYZ", xyz(2)),
+                (b"rTRC", trc.clone()),
+                (b"gTRC", trc.clone()),
+                (b"bTRC", trc),
+            ],
+        );
+        Profile::parse(data).expect("Invalid generated profile")
+    }
+
+    /// Built-in sRGB profile
+    pub fn srgb() -> Profile {
+        Profile::matrix_trc("sRGB", SRGB_D50, Curve::srgb())
+    }
+
+    /// Built-in linear sRGB profile
+    pub fn linear_srgb() -> Profile {
+        Profile::matrix_trc("Linear sRGB", SRGB_D50, Curve::Identity)
+    }
+
+    /// Get the encoded profile, suitable for embedding in an image
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Number of channels in the data color space
+    pub fn channels(&self) -> usize {
+        match &self.color_space {
+            b"GRAY" => 1,
+            b"2CLR" => 2,
+            b"CMYK" | b"4CLR" => 4,
+            b"5CLR" => 5,
+            b"6CLR" => 6,
+            b"7CLR" => 7,
+            b"8CLR" => 8,
+            _ => 3,
+        }
+    }
+
+    /// Convert normalized device values to D50 XYZ
+    pub fn to_pcs(&self, device: &[f64]) -> [f64; 3] {
+        let lab = &self.pcs == b"Lab ";
+
+        if let Some(lut) = &self.a2b {
+            let mut input = device.to_vec();
+            input.resize(self.channels(), 0.0);
+            return lut.decode_pcs(&lut.eval(&input), lab);
+        }
+
+        if self.trc.len() == 1 {
+            let y = self.trc[0].eval(device.first().copied().unwrap_or_default());
+            return [D50[0] * y, D50[1] * y, D50[2] * y];
+        }
+
+        let linear = [
+            self.trc[0].eval(device.first().copied().unwrap_or_default()),
+            self.trc[1].eval(device.get(1).copied().unwrap_or_default()),
+            self.trc[2].eval(device.get(2).copied().unwrap_or_default()),
+        ];
+        mul(self.matrix.as_ref().unwrap(), linear)
+    }
+
+    /// Convert D50 XYZ to normalized device values
+    pub fn from_pcs(&self, xyz: [f64; 3], device: &mut [f64]) {
+        let lab = &self.pcs == b"Lab ";
+
+        if let Some(lut) = &self.b2a {
+            let out = lut.eval(&lut.encode_pcs(xyz, lab));
+            for (d, x) in device.iter_mut().zip(out) {
+                *d = x;
+            }
+            return;
+        }
+
+        if self.trc.len() == 1 {
+            if let Some(d) = device.first_mut() {
+                *d = self.trc[0].inverse(xyz[1]);
+            }
+            return;
+        }
+
+        let linear = match &self.inverse_matrix {
+            Some(m) => mul(m, xyz),
+            None => return,
+        };
+        for (i, d) in device.iter_mut().enumerate().take(3) {
+            *d = self.trc[i].inverse(linear[i]);
+        }
+    }
+}
+
+fn parse_text(d: &[u8]) -> Result<String, Error> {
+    match &signature(d, 0)? {
+        b"desc" => {
+            let n = u32be(d, 8)? as usize;
+            let s = d
+                .get(12..12 + n)
+                .ok_or_else(|| err("invalid description"))?;
+            let s = s.split(|c| *c == 0).next().unwrap_or_default();
+            Ok(String::from_utf8_lossy(s).to_string())
+        }
+        b"mluc" => {
+            let len = u32be(d, 20)? as usize;
+            let offset = u32be(d, 24)? as usize;
+            let s = d
+                .get(offset..offset + len)
+                .ok_or_else(|| err("invalid description"))?;
+            let s: Vec<u16> = s
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            Ok(String::from_utf16_lossy(&s))
+        }
+        b"text" => {
+            let s = d[8..].split(|c| *c == 0).next().unwrap_or_default();
+            Ok(String::from_utf8_lossy(s).to_string())
+        }
+        _ => Err(err("unknown text type")),
+    }
+}
+
+fn text_bytes(s: &str) -> Vec<u8> {
+    let s: Vec<u8> = s.encode_utf16().flat_map(|c| c.to_be_bytes()).collect();
+    let mut d = b"mluc\0\0\0\0".to_vec();
+    d.extend_from_slice(&1u32.to_be_bytes());
+    d.extend_from_slice(&12u32.to_be_bytes());
+    d.extend_from_slice(b"enUS");
+    d.extend_from_slice(&(s.len() as u32).to_be_bytes());
+    d.extend_from_slice(&28u32.to_be_bytes());
+    d.extend_from_slice(&s);
+    d
+}
+
+fn xyz_bytes(xyz: [f64; 3]) -> Vec<u8> {
+    let mut d = b"XYZ \0\0\0\0".to_vec();
+    for x in xyz.iter() {
+        d.extend_from_slice(&((x * 65536.0).round() as i32).to_be_bytes());
+    }
+    d
+}
+
+/// Assemble a v4 display profile, tags with identical data share storage
+fn build(color_space: &[u8; 4], tags: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut header = vec![0u8; 128];
+    header[8] = 4;
+    header[9] = 0x30;
+    header[12..16].copy_from_slice(b"mntr");
+    header[16..20].copy_from_slice(color_space);
+    header[20..24].copy_from_slice(b"XYZ ");
+    header[36..40].copy_from_slice(b"acsp");
+    for (i, x) in D50.iter().enumerate() {
+        header[68 + i * 4..72 + i * 4]
+            .copy_from_slice(&((x * 65536.0).round() as i32).to_be_bytes());
+    }
+
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut body: Vec<u8> = Vec::new();
+    let mut written: Vec<(&Vec<u8>, usize)> = Vec::new();
+    let start = 128 + 4 + tags.len() * 12;
+
+    for (sig, data) in tags {
+        let offset = match written.iter().find(|(d, _)| *d == data) {
+            Some((_, offset)) => *offset,
+            None => {
+                let offset = start + body.len();
+                body.extend_from_slice(data);
+                body.resize((body.len() + 3) & !3, 0);
+                written.push((data, offset));
+                offset
+            }
+        };
+        table.extend_from_slice(*sig);
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    }
+
+    let mut data = header;
+    data.extend_from_slice(&table);
+    data.extend_from_slice(&body);
+    let len = (data.len() as u32).to_be_bytes();
+    data[0..4].copy_from_slice(&len);
+    data
+}
+
+/// Converts image data between two ICC profiles
+///
+/// Channels beyond those described by the destination profile, like alpha, are copied from the
+/// input
+pub struct Transform {
+    src: Profile,
+    dest: Profile,
+}
+
+impl Transform {
+    /// Create a transform from `src` to `dest`
+    pub fn new(src: Profile, dest: Profile) -> Transform {
+        Transform { src, dest }
+    }
+
+    /// Create a transform from `src` to sRGB
+    pub fn to_srgb(src: Profile) -> Transform {
+        Transform::new(src, Profile::srgb())
+    }
+
+    /// Create a transform from sRGB to `dest`
+    pub fn from_srgb(dest: Profile) -> Transform {
+        Transform::new(Profile::srgb(), dest)
+    }
+
+    /// Source profile
+    pub fn src(&self) -> &Profile {
+        &self.src
+    }
+
+    /// Destination profile
+    pub fn dest(&self) -> &Profile {
+        &self.dest
+    }
+
+    /// Convert normalized device values from the source profile to the destination profile
+    pub fn convert(&self, input: &[f64], output: &mut [f64]) {
+        self.dest.from_pcs(self.src.to_pcs(input), output)
+    }
+}
+
+impl Filter for Transform {
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        let channels = self.dest.channels();
+        if c >= channels {
+            return input[0].get_f(x, y, c);
+        }
+
+        let px = input[0].get_pixel(x, y);
+        let mut out = vec![0.0; channels];
+        self.convert(px.as_ref(), &mut out);
+        out[c]
+    }
+
+    fn compute_pixel<C: Color>(
+        &self,
+        x: usize,
+        y: usize,
+        input: &[&impl GenericImage],
+        out: &mut Pixel<C>,
+    ) {
+        let px = input[0].get_pixel(x, y);
+        let channels = self.dest.channels().min(out.len());
+        self.convert(px.as_ref(), &mut out.as_mut()[..channels]);
+        for (c, f) in out.iter_mut().enumerate().skip(channels) {
+            *f = input[0].get_f(x, y, c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: &[f64], b: &[f64], eps: f64) -> bool {
+        a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < eps)
+    }
+
+    #[test]
+    fn test_icc_srgb() {
+        let srgb = Profile::srgb();
+        let parsed = Profile::parse(srgb.as_bytes()).unwrap();
+        assert_eq!(parsed, srgb);
+        assert_eq!(parsed.description.as_deref(), Some("sRGB"));
+        assert_eq!(parsed.version, (4, 3));
+
+        assert!(close(&srgb.to_pcs(&[1.0, 1.0, 1.0]), &D50, 1e-3));
+
+        let mut out = [0.0; 3];
+        for input in &[[0.2, 0.5, 0.8], [1.0, 0.0, 0.0], [0.04, 0.03, 0.02]] {
+            srgb.from_pcs(srgb.to_pcs(input), &mut out);
+            assert!(close(input, &out, 1e-4));
+        }
+
+        let t = Transform::new(Profile::srgb(), Profile::linear_srgb());
+        t.convert(&[0.5, 0.5, 0.5], &mut out);
+        assert!(close(&out, &[0.214, 0.214, 0.214], 1e-3));
+    }
+
+    #[test]
+    fn test_icc_lut16() {
+        // A 2x2x2 lut16 mapping RGB to XYZ, interpolating a linear function is exact
+        let m = SRGB_D50;
+        let mut lut = b"mft2\0\0\0\0".to_vec();
+        lut.extend_from_slice(&[3, 3, 2, 0]);
+        for i in 0..9 {
+            let x: i32 = if i % 4 == 0 { 65536 } else { 0 };
+            lut.extend_from_slice(&x.to_be_bytes());
+        }
+        lut.extend_from_slice(&2u16.to_be_bytes());
+        lut.extend_from_slice(&2u16.to_be_bytes());
+        for _ in 0..3 {
+            lut.extend_from_slice(&0u16.to_be_bytes());
+            lut.extend_from_slice(&65535u16.to_be_bytes());
+        }
+        for r in 0..2 {
+            for g in 0..2 {
+                for b in 0..2 {
+                    let xyz = mul(&m, [r as f64, g as f64, b as f64]);
+                    for x in xyz.iter() {
+                        let v = (x * 32768.0 / 65535.0 * 65535.0).round() as u16;
+                        lut.extend_from_slice(&v.to_be_bytes());
+                    }
+                }
+            }
+        }
+        for _ in 0..3 {
+            lut.extend_from_slice(&0u16.to_be_bytes());
+            lut.extend_from_slice(&65535u16.to_be_bytes());
+        }
+
+        let data = build(b"RGB ", &[(b"A2B0", lut)]);
+        let profile = Profile::parse(data).unwrap();
+        let expected = mul(&m, [0.25, 0.5, 0.75]);
+        assert!(close(&profile.to_pcs(&[0.25, 0.5, 0.75]), &expected, 1e-3));
+    }
+
+    #[test]
+    fn test_icc_invalid_clut() {
+        // Malformed lut16 headers are rejected instead of panicking
+        let lut = |inputs: u8, grid: u8| {
+            let mut lut = b"mft2\0\0\0\0".to_vec();
+            lut.extend_from_slice(&[inputs, 3, grid, 0]);
+            lut.extend_from_slice(&[0; 36]);
+            lut.extend_from_slice(&2u16.to_be_bytes());
+            lut.extend_from_slice(&2u16.to_be_bytes());
+            lut.extend_from_slice(&[0; 4096]);
+            Lut::parse(&lut, false)
+        };
+
+        assert!(lut(3, 2).is_ok());
+        assert!(lut(3, 0).is_err());
+        assert!(lut(3, 1).is_err());
+        assert!(lut(3, 255).is_err());
+        assert!(lut(16, 2).is_err());
+        assert!(lut(64, 2).is_err());
+        assert!(lut(15, 255).is_err());
+    }
+
+    #[test]
+    fn test_icc_jpeg_chunks() {
+        let profile = Profile::srgb();
+        let (a, b) = profile.as_bytes().split_at(100);
+
+        let mut jpeg = vec![0xff, 0xd8];
+        for (i, chunk) in [(2u8, b), (1u8, a)].iter() {
+            jpeg.extend_from_slice(&[0xff, 0xe2]);
+            jpeg.extend_from_slice(&((chunk.len() + 16) as u16).to_be_bytes());
+            jpeg.extend_from_slice(b"ICC_PROFILE\0");
+            jpeg.extend_from_slice(&[*i, 2]);
+            jpeg.extend_from_slice(chunk);
+        }
+        jpeg.extend_from_slice(&[0xff, 0xda]);
+
+        let data = io::container::icc_profile(&jpeg).unwrap().unwrap();
+        assert_eq!(Profile::parse(data).unwrap(), profile);
+    }
+
+    #[test]
+    fn test_icc_transform_filter() {
+        let mut image: Image<f32, Rgba> = Image::new(4, 4);
+        image.for_each(|(x, y), px| {
+            px[0] = x as f32 / 4.0;
+            px[1] = y as f32 / 4.0;
+            px[2] = 0.5;
+            px[3] = 0.25;
+        });
+
+        let mut dest = image.new_like();
+        Transform::new(Profile::srgb(), Profile::srgb()).eval(&mut dest, &[&image]);
+        for (a, b) in image.data.iter().zip(dest.data.iter()) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+}
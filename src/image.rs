@@ -35,6 +35,36 @@ impl Region {
 pub struct Meta<T: Type, C: Color> {
     pub width: usize,
     pub height: usize,
+
+    /// Horizontal origin of the data window
+    pub x: isize,
+
+    /// Vertical origin of the data window
+    pub y: isize,
+
+    /// Horizontal origin of the display window
+    pub full_x: isize,
+
+    /// Vertical origin of the display window
+    pub full_y: isize,
+
+    /// Width of the display window
+    pub full_width: usize,
+
+    /// Height of the display window
+    pub full_height: usize,
+
+    /// Pixel aspect ratio (width / height)
+    pub pixel_aspect: io::exif::Rational,
+
+    /// Horizontal and vertical resolution in pixels per inch, if available
+    pub dpi: Option<(io::exif::Rational, io::exif::Rational)>,
+
+    /// EXIF metadata, if available
+    pub exif: Option<io::exif::Exif>,
+
+    /// Embedded ICC profile, if available, see `icc::Profile` to parse it
+    pub icc_profile: Option<Vec<u8>>,
     _type: PhantomData<T>,
     _color: PhantomData<C>,
 }
@@ -44,6 +74,16 @@ impl<T: Type, C: Color> Meta<T, C> {
         Meta {
             width: w,
             height: h,
+            x: 0,
+            y: 0,
+            full_x: 0,
+            full_y: 0,
+            full_width: w,
+            full_height: h,
+            pixel_aspect: io::exif::Rational::new(1, 1),
+            dpi: None,
+            exif: None,
+            icc_profile: None,
             _type: PhantomData,
             _color: PhantomData,
         }
@@ -53,6 +93,87 @@ impl<T: Type, C: Color> Meta<T, C> {
         C::ALPHA
     }
 
+    /// Returns true when color channels are premultiplied by alpha
+    pub fn is_premultiplied(&self) -> bool {
+        C::PREMULTIPLIED
+    }
+
+    /// Transfer function of the color channels
+    pub fn transfer(&self) -> TransferFunction {
+        C::TRANSFER
+    }
+
+    /// Decode a normalized channel value to linear light, see `Color::to_linear`
+    pub fn decode_transfer(&self, c: usize, x: f64) -> f64 {
+        C::to_linear(c, x)
+    }
+
+    /// Encode a normalized linear light channel value, see `Color::from_linear`
+    pub fn encode_transfer(&self, c: usize, x: f64) -> f64 {
+        C::from_linear(c, x)
+    }
+
+    /// Index of the alpha channel, if there is one
+    pub fn alpha_channel(&self) -> Option<usize> {
+        if C::ALPHA {
+            Some(C::ALPHA_CHANNEL)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the data window as (x, y, width, height)
+    pub fn data_window(&self) -> (isize, isize, usize, usize) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    /// Returns the display window as (x, y, width, height)
+    pub fn display_window(&self) -> (isize, isize, usize, usize) {
+        (self.full_x, self.full_y, self.full_width, self.full_height)
+    }
+
+    /// Returns true when the data window doesn't match the display window
+    pub fn has_display_window(&self) -> bool {
+        self.data_window() != self.display_window()
+    }
+
+    /// Copy all metadata to another type or color
+    pub(crate) fn cast<U: Type, D: Color>(self) -> Meta<U, D> {
+        Meta {
+            width: self.width,
+            height: self.height,
+            x: self.x,
+            y: self.y,
+            full_x: self.full_x,
+            full_y: self.full_y,
+            full_width: self.full_width,
+            full_height: self.full_height,
+            pixel_aspect: self.pixel_aspect,
+            dpi: self.dpi,
+            exif: self.exif,
+            icc_profile: self.icc_profile,
+            _type: PhantomData,
+            _color: PhantomData,
+        }
+    }
+
+    /// Copy the window, aspect ratio and resolution to metadata with a different type or color
+    pub fn with_geometry<U: Type, D: Color>(&self, width: usize, height: usize) -> Meta<U, D> {
+        Meta {
+            width,
+            height,
+            x: self.x,
+            y: self.y,
+            full_x: self.full_x,
+            full_y: self.full_y,
+            full_width: self.full_width,
+            full_height: self.full_height,
+            pixel_aspect: self.pixel_aspect,
+            dpi: self.dpi,
+            ..Meta::new(width, height)
+        }
+    }
+
     pub fn color_name(&self) -> &str {
         C::NAME
     }
@@ -73,6 +194,24 @@ pub struct Image<T: Type, C: Color> {
     pub data: Vec<T>,
 }
 
+impl<T: Type, C: Premultiply> Image<T, C> {
+    /// Multiply color channels by alpha
+    pub fn premultiply(&self) -> Image<T, C::Premultiplied> {
+        let mut dest = self.clone();
+        dest.apply_alpha(|x, alpha| x * alpha);
+        dest.with_color()
+    }
+}
+
+impl<T: Type, C: Unpremultiply> Image<T, C> {
+    /// Divide color channels by alpha
+    pub fn unpremultiply(&self) -> Image<T, C::Straight> {
+        let mut dest = self.clone();
+        dest.apply_alpha(color::unpremultiply);
+        dest.with_color()
+    }
+}
+
 /// Hash is used for content-based hashing
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
 pub struct Hash(u128);
@@ -136,63 +275,164 @@ impl<T: Type, C: Color> std::ops::IndexMut<(usize, usize, usize)> for Image<T, C
     }
 }
 
+/// Round an intermediate result of an integer operation, floating point values are left as is
+#[inline]
+fn round_value<T: Type>(rounding: Rounding, x: f64) -> f64 {
+    if T::is_float() {
+        x
+    } else {
+        rounding.round(x)
+    }
+}
+
+macro_rules! image_op {
+    ($name:ident, $f:ident, $assign:ident, $fassign:ident, $op:tt, $rounding:expr) => {
+        impl<'a, T: Type, C: Color> std::ops::$assign<&'a Image<T, C>> for Image<T, C> {
+            fn $fassign(&mut self, other: &'a Image<T, C>) {
+                assert_eq!(
+                    self.shape(),
+                    other.shape(),
+                    "Image dimensions don't match"
+                );
+                let _ = self.zip_values(other, |a, b| round_value::<T>($rounding, a $op b));
+            }
+        }
+
+        impl<T: Type, C: Color> std::ops::$assign<T> for Image<T, C> {
+            fn $fassign(&mut self, other: T) {
+                let other = other.to_f64();
+                self.map_values(|a| round_value::<T>($rounding, a $op other));
+            }
+        }
+
+        impl<T: Type, C: Color> std::ops::$assign<Pixel<C>> for Image<T, C> {
+            fn $fassign(&mut self, other: Pixel<C>) {
+                self.for_each(|_, px| {
+                    for (x, p) in px.iter_mut().zip(other.iter()) {
+                        *x = T::from_norm(x.to_norm() $op p);
+                    }
+                });
+            }
+        }
+
+        impl<'a, T: Type, C: Color> std::ops::$name<&'a Image<T, C>> for Image<T, C> {
+            type Output = Image<T, C>;
+
+            fn $f(mut self, other: &'a Image<T, C>) -> Image<T, C> {
+                std::ops::$assign::$fassign(&mut self, other);
+                self
+            }
+        }
+
+        impl<T: Type, C: Color> std::ops::$name<Image<T, C>> for Image<T, C> {
+            type Output = Image<T, C>;
+
+            fn $f(self, other: Image<T, C>) -> Image<T, C> {
+                self $op &other
+            }
+        }
+
+        impl<'a, 'b, T: Type, C: Color> std::ops::$name<&'b Image<T, C>> for &'a Image<T, C> {
+            type Output = Image<T, C>;
+
+            fn $f(self, other: &'b Image<T, C>) -> Image<T, C> {
+                self.clone() $op other
+            }
+        }
+
+        impl<T: Type, C: Color> std::ops::$name<T> for Image<T, C> {
+            type Output = Image<T, C>;
+
+            fn $f(mut self, other: T) -> Image<T, C> {
+                std::ops::$assign::$fassign(&mut self, other);
+                self
+            }
+        }
+
+        impl<'a, T: Type, C: Color> std::ops::$name<T> for &'a Image<T, C> {
+            type Output = Image<T, C>;
+
+            fn $f(self, other: T) -> Image<T, C> {
+                self.clone() $op other
+            }
+        }
+
+        impl<T: Type, C: Color> std::ops::$name<Pixel<C>> for Image<T, C> {
+            type Output = Image<T, C>;
+
+            fn $f(mut self, other: Pixel<C>) -> Image<T, C> {
+                std::ops::$assign::$fassign(&mut self, other);
+                self
+            }
+        }
+
+        impl<'a, T: Type, C: Color> std::ops::$name<Pixel<C>> for &'a Image<T, C> {
+            type Output = Image<T, C>;
+
+            fn $f(self, other: Pixel<C>) -> Image<T, C> {
+                self.clone() $op other
+            }
+        }
+    };
+}
+
+// Arithmetic between images and with scalars of type `T` operates on the stored values, with
+// integer results saturating at the limits of the type. Integer division truncates like it does
+// for Rust integers, but dividing by zero doesn't panic: the result saturates at `MAX` or `MIN`
+// depending on the sign of the dividend, and `0 / 0` and any remainder by zero give 0.
+// Arithmetic with a `Pixel` operates on normalized values, the same way arithmetic between
+// pixels does
+image_op!(Add, add, AddAssign, add_assign, +, Rounding::Nearest);
+image_op!(Sub, sub, SubAssign, sub_assign, -, Rounding::Nearest);
+image_op!(Mul, mul, MulAssign, mul_assign, *, Rounding::Nearest);
+image_op!(Div, div, DivAssign, div_assign, /, Rounding::Truncate);
+image_op!(Rem, rem, RemAssign, rem_assign, %, Rounding::Nearest);
+
 impl<T: Type, C: Color> Image<T, C> {
     /// Create a new image
     pub fn new(width: usize, height: usize) -> Image<T, C> {
         let data = vec![T::default(); width * height * C::CHANNELS];
         Image {
-            meta: Meta {
-                width,
-                height,
-                _type: PhantomData,
-                _color: PhantomData,
-            },
+            meta: Meta::new(width, height),
             data,
         }
     }
 
     /// Get image hash
-#[cfg(feature = "transforms")]
+    #[cfg(feature = "transforms")]
     pub fn hash(&self) -> Hash {
         let mut small: Image<T, C> = Image::new(16, 8);
         crate::transform::resize(self, 16, 8).eval(&mut small, &[self]);
-        let mut hash = 0u128;
-        let mut index = 0;
-        let mut px = Pixel::new();
-        for j in 0..8 {
-            for i in 0..16 {
-                small.pixel_at(i, j, &mut px);
-                let avg: f64 = px.iter().sum();
-                let f = avg / C::CHANNELS as f64;
-                if f > 0.5 {
-                    hash |= 1 << index
-                } else {
-                    hash &= !(1 << index)
-                }
-                index += 1
-            }
-        }
+        let hash = small.reduce(&reduce::ComputeHash);
         Hash(hash)
     }
 
     /// Create a new image with the same size, type and color
     pub fn new_like(&self) -> Image<T, C> {
-        Image::new(self.meta.width, self.meta.height)
+        let mut image = Image::new(self.meta.width, self.meta.height);
+        image.meta = self.meta.with_geometry(self.meta.width, self.meta.height);
+        image
     }
 
     /// Create a new image with the same size and color as an existing image with the given type
     pub fn new_like_with_type<U: Type>(&self) -> Image<U, C> {
-        Image::new(self.meta.width, self.meta.height)
+        let mut image = Image::new(self.meta.width, self.meta.height);
+        image.meta = self.meta.with_geometry(self.meta.width, self.meta.height);
+        image
     }
 
     /// Create a new image with the same size and type as an existing image with the given color
     pub fn new_like_with_color<D: Color>(&self) -> Image<T, D> {
-        Image::new(self.meta.width, self.meta.height)
+        let mut image = Image::new(self.meta.width, self.meta.height);
+        image.meta = self.meta.with_geometry(self.meta.width, self.meta.height);
+        image
     }
 
     /// Create a new image with the same size as an existing image with the given type and color
     pub fn new_like_with_type_and_color<U: Type, D: Color>(&self) -> Image<U, D> {
-        Image::new(self.meta.width, self.meta.height)
+        let mut image = Image::new(self.meta.width, self.meta.height);
+        image.meta = self.meta.with_geometry(self.meta.width, self.meta.height);
+        image
     }
 
     /// Maximum value for image type
@@ -359,16 +599,42 @@ impl<T: Type, C: Color> Image<T, C> {
 
     /// Open an image from disk
     pub fn open(path: impl AsRef<std::path::Path>) -> Result<Image<T, C>, Error> {
+        Self::open_with(path, &io::OpenOptions::default())
+    }
+
+    /// Open an image from disk using the given options
+    pub fn open_with(
+        path: impl AsRef<std::path::Path>,
+        options: &io::OpenOptions,
+    ) -> Result<Image<T, C>, Error> {
+        let path = path.as_ref();
+
         #[cfg(feature = "oiio")]
-        {
+        let mut image: Image<T, C> = {
             let input = io::Input::open(path)?;
-            input.read()
-        }
+            input.read()?
+        };
 
         #[cfg(not(feature = "oiio"))]
-        {
-            let x = io::magick::read(path)?;
-            Ok(x)
+        let mut image: Image<T, C> = io::magick::read(path)?;
+
+        image.read_file_metadata(path, options);
+        Ok(image)
+    }
+
+    /// Load EXIF and ICC metadata from the file at `path`
+    pub(crate) fn read_file_metadata(&mut self, path: &std::path::Path, options: &io::OpenOptions) {
+        // Missing or malformed metadata should never prevent an image from loading
+        if let Ok(data) = std::fs::read(path) {
+            if let Ok(Some(mut exif)) = io::exif::Exif::from_bytes(&data) {
+                if options.is_auto_orient() {
+                    *self = self.orient(exif.orientation);
+                    exif.orientation = io::exif::Orientation::Normal;
+                }
+                self.meta.exif = Some(exif);
+            }
+
+            self.meta.icc_profile = io::container::icc_profile(&data).unwrap_or_default();
         }
     }
 
@@ -376,7 +642,10 @@ impl<T: Type, C: Color> Image<T, C> {
     pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
         #[cfg(feature = "oiio")]
         {
-            let output = io::Output::create(path)?;
+            let mut output = io::Output::create(path)?;
+            if let Some(profile) = &self.meta.icc_profile {
+                output.spec_mut().set_icc_profile(profile);
+            }
             output.write(self)
         }
 
@@ -608,6 +877,34 @@ impl<T: Type, C: Color> Image<T, C> {
             });
     }
 
+    /// Apply `f` to every stored value, results are saturated at the limits of `T`
+    pub fn map_values<F: Sync + Send + Fn(f64) -> f64>(&mut self, f: F) {
+        self.for_each(|_, px| {
+            px.iter_mut().for_each(|x| *x = T::from_f64(f(x.to_f64())));
+        });
+    }
+
+    /// Combine every stored value with the matching value of `other`, results are saturated at
+    /// the limits of `T`. Returns `Error::InvalidDimensions` with the shape of `other` when the
+    /// images have different dimensions
+    pub fn zip_values<F: Sync + Send + Fn(f64, f64) -> f64>(
+        &mut self,
+        other: &Image<T, C>,
+        f: F,
+    ) -> Result<(), Error> {
+        if self.shape() != other.shape() {
+            let (width, height, channels) = other.shape();
+            return Err(Error::InvalidDimensions(width, height, channels));
+        }
+
+        self.for_each2(other, |_, a, b| {
+            a.iter_mut()
+                .zip(b)
+                .for_each(|(a, b)| *a = T::from_f64(f(a.to_f64(), b.to_f64())));
+        });
+        Ok(())
+    }
+
     /// Iterate over pixels, with a mutable closure
     pub fn each_pixel<F: Sync + Send + FnMut((usize, usize), &[T])>(&self, mut f: F) {
         let (width, _height, channels) = self.shape();
@@ -637,26 +934,180 @@ impl<T: Type, C: Color> Image<T, C> {
             });
     }
 
-    /// Copy a region of an image to a new image
+    /// Create a new image, filling each pixel from the source location returned by `f`. When `swap`
+    /// is true the width and height are exchanged. `margins` maps the distance between the data
+    /// window and the display window, given as `[left, top, right, bottom]`, to the new left and
+    /// top margins
+    fn remap(
+        &self,
+        swap: bool,
+        f: impl Sync + Send + Fn(usize, usize) -> (usize, usize),
+        margins: impl Fn([isize; 4]) -> (isize, isize),
+    ) -> Image<T, C> {
+        let meta = &self.meta;
+        let (left, top) = margins([
+            meta.x - meta.full_x,
+            meta.y - meta.full_y,
+            meta.full_x + meta.full_width as isize - meta.x - meta.width as isize,
+            meta.full_y + meta.full_height as isize - meta.y - meta.height as isize,
+        ]);
+
+        let mut dest_meta = Meta {
+            x: meta.full_x + left,
+            y: meta.full_y + top,
+            ..meta.clone()
+        };
+
+        if swap {
+            dest_meta.width = meta.height;
+            dest_meta.height = meta.width;
+            dest_meta.full_width = meta.full_height;
+            dest_meta.full_height = meta.full_width;
+            dest_meta.pixel_aspect =
+                io::exif::Rational::new(meta.pixel_aspect.den, meta.pixel_aspect.num);
+            dest_meta.dpi = meta.dpi.map(|(x, y)| (y, x));
+        }
+
+        let mut dest = Image::new(dest_meta.width, dest_meta.height);
+        dest.meta = dest_meta;
+        dest.for_each(|(x, y), px| {
+            let (a, b) = f(x, y);
+            px.copy_from_slice(self.get(a, b));
+        });
+        dest
+    }
+
+    /// Mirror the image horizontally
+    pub fn flip_horizontal(&self) -> Image<T, C> {
+        let (width, _, _) = self.shape();
+        self.remap(false, |x, y| (width - 1 - x, y), |[_, t, r, _]| (r, t))
+    }
+
+    /// Mirror the image vertically
+    pub fn flip_vertical(&self) -> Image<T, C> {
+        let (_, height, _) = self.shape();
+        self.remap(false, |x, y| (x, height - 1 - y), |[l, _, _, b]| (l, b))
+    }
+
+    /// Rotate the image 90 degrees clockwise
+    pub fn rotate90(&self) -> Image<T, C> {
+        let (_, height, _) = self.shape();
+        self.remap(true, |x, y| (y, height - 1 - x), |[l, _, _, b]| (b, l))
+    }
+
+    /// Rotate the image 180 degrees
+    pub fn rotate180(&self) -> Image<T, C> {
+        let (width, height, _) = self.shape();
+        self.remap(
+            false,
+            |x, y| (width - 1 - x, height - 1 - y),
+            |[_, _, r, b]| (r, b),
+        )
+    }
+
+    /// Rotate the image 270 degrees clockwise
+    pub fn rotate270(&self) -> Image<T, C> {
+        let (width, _, _) = self.shape();
+        self.remap(true, |x, y| (width - 1 - y, x), |[_, t, r, _]| (t, r))
+    }
+
+    /// Mirror the image across the top-left to bottom-right diagonal
+    pub fn transpose(&self) -> Image<T, C> {
+        self.remap(true, |x, y| (y, x), |[l, t, _, _]| (t, l))
+    }
+
+    /// Mirror the image across the top-right to bottom-left diagonal
+    pub fn transverse(&self) -> Image<T, C> {
+        let (width, height, _) = self.shape();
+        self.remap(
+            true,
+            |x, y| (width - 1 - y, height - 1 - x),
+            |[_, _, r, b]| (b, r),
+        )
+    }
+
+    /// Apply an EXIF orientation using lossless flips and rotations
+    pub fn orient(&self, orientation: io::exif::Orientation) -> Image<T, C> {
+        use io::exif::Orientation::*;
+        match orientation {
+            Normal => self.clone(),
+            FlipHorizontal => self.flip_horizontal(),
+            Rotate180 => self.rotate180(),
+            FlipVertical => self.flip_vertical(),
+            Transpose => self.transpose(),
+            Rotate90 => self.rotate90(),
+            Transverse => self.transverse(),
+            Rotate270 => self.rotate270(),
+        }
+    }
+
+    /// Copy a region of an image to a new image, the data window of the new image is offset so
+    /// it stays in the same place relative to the display window
     pub fn crop(&self, roi: Region) -> Image<T, C> {
         let mut dest = Image::new(roi.width, roi.height);
+        dest.meta = Meta {
+            width: roi.width,
+            height: roi.height,
+            x: self.meta.x + roi.x as isize,
+            y: self.meta.y + roi.y as isize,
+            ..self.meta.clone()
+        };
         dest.copy_region(Region::new(0, 0, roi.width, roi.height), self, roi.x, roi.y);
         dest
     }
 
-    /// Copy into a region from another image starting at the given offset
+    /// Copy into a region from another image starting at the given offset, `roi` is relative to
+    /// the data window of `self` and the offsets are relative to the data window of `other`.
+    /// Pixels that fall outside of the data window of `other` are left unchanged
     pub fn copy_region(&mut self, roi: Region, other: &Image<T, C>, x_offs: usize, y_offs: usize) {
         self.for_each_region(roi, |(x, y), px| {
-            px.copy_from_slice(other.get(x - roi.x + x_offs, y - roi.y + y_offs));
+            let (a, b) = (x - roi.x + x_offs, y - roi.y + y_offs);
+            if other.in_bounds(a, b) {
+                px.copy_from_slice(other.get(a, b));
+            }
         });
     }
 
+    /// Copy the overlapping part of `other` into `self`, aligning both images using their data
+    /// window origins
+    pub fn copy_window(&mut self, other: &Image<T, C>) {
+        let x0 = self.meta.x.max(other.meta.x);
+        let y0 = self.meta.y.max(other.meta.y);
+        let x1 =
+            (self.meta.x + self.meta.width as isize).min(other.meta.x + other.meta.width as isize);
+        let y1 = (self.meta.y + self.meta.height as isize)
+            .min(other.meta.y + other.meta.height as isize);
+        if x1 <= x0 || y1 <= y0 {
+            return;
+        }
+
+        let roi = Region::new(
+            (x0 - self.meta.x) as usize,
+            (y0 - self.meta.y) as usize,
+            (x1 - x0) as usize,
+            (y1 - y0) as usize,
+        );
+        self.copy_region(
+            roi,
+            other,
+            (x0 - other.meta.x) as usize,
+            (y0 - other.meta.y) as usize,
+        );
+    }
+
+    /// Get a view of part of an image without copying, `roi` is clipped to the image bounds
+    pub fn view(&self, roi: Region) -> ImageView<'_, T, C> {
+        GenericImage::view(self, roi)
+    }
+
+    /// Get a mutable view of part of an image without copying, `roi` is clipped to the image
+    /// bounds
+    pub fn view_mut(&mut self, roi: Region) -> ImageViewMut<'_, T, C> {
+        GenericImageMut::view_mut(self, roi)
+    }
+
     /// Apply a filter
-    pub fn apply(
-        &mut self,
-        input: &[&Image<impl Type, impl Color>],
-        filter: impl Filter,
-    ) -> &mut Self {
+    pub fn apply(&mut self, input: &[&impl GenericImage], filter: impl Filter) -> &mut Self {
         filter.eval(self, input);
         self
     }
@@ -668,6 +1119,149 @@ impl<T: Type, C: Color> Image<T, C> {
         dest
     }
 
+    /// Convert image type/color using the given rounding mode for integer types
+    pub fn convert_rounded<U: Type, D: Color>(&self, rounding: Rounding) -> Image<U, D> {
+        self.convert_into(rounding, None)
+    }
+
+    /// Convert image type/color, also returning the number of values that were clipped because
+    /// they don't fit in the range of `U`
+    pub fn convert_checked<U: Type, D: Color>(&self, rounding: Rounding) -> (Image<U, D>, usize) {
+        let clipped = std::sync::atomic::AtomicUsize::new(0);
+        let dest = self.convert_into(rounding, Some(&clipped));
+        (dest, clipped.into_inner())
+    }
+
+    fn convert_into<U: Type, D: Color>(
+        &self,
+        rounding: Rounding,
+        clipped: Option<&std::sync::atomic::AtomicUsize>,
+    ) -> Image<U, D> {
+        let mut dest: Image<U, D> = self.new_like_with_type_and_color();
+        let filter = Convert::<D>::new();
+        dest.for_each(|(x, y), px| {
+            let mut out = Pixel::<D>::new();
+            filter.compute_pixel(x, y, &[self], &mut out);
+            for (dest, f) in px.iter_mut().zip(out.iter()) {
+                if let Some(clipped) = clipped {
+                    if U::is_clipped(*f) {
+                        clipped.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    }
+                }
+                *dest = U::from_norm_rounded(*f, rounding);
+            }
+        });
+        dest
+    }
+
+    /// Apply `f(value, alpha)` to each color channel, does nothing if there is no alpha channel
+    pub(crate) fn apply_alpha(&mut self, f: impl Sync + Send + Fn(f64, f64) -> f64) {
+        if !C::ALPHA {
+            return;
+        }
+
+        let index = C::ALPHA_CHANNEL;
+        self.for_each(|_, px| {
+            let alpha = px[index].to_norm();
+            for (c, x) in px.iter_mut().enumerate() {
+                if c != index {
+                    *x = T::from_norm(f(x.to_norm(), alpha));
+                }
+            }
+        });
+    }
+
+    /// Reorder channels from the layout used on disk, see `Color::CHANNEL_ORDER`
+    pub(crate) fn restore_channel_order(&mut self) {
+        let order = C::CHANNEL_ORDER;
+        if order.is_empty() {
+            return;
+        }
+
+        let mut tmp = vec![T::default(); C::CHANNELS];
+        self.each_pixel_mut(|_, px| {
+            tmp.copy_from_slice(px);
+            for (dest, src) in px.iter_mut().zip(order) {
+                *dest = tmp[*src];
+            }
+        });
+    }
+
+    /// Get an image with channels in the layout used on disk, see `Color::CHANNEL_ORDER`
+    pub(crate) fn disk_channel_order(&self) -> std::borrow::Cow<'_, Image<T, C>> {
+        let order = C::CHANNEL_ORDER;
+        if order.is_empty() {
+            return std::borrow::Cow::Borrowed(self);
+        }
+
+        let mut dest = self.clone();
+        dest.for_each2(self, |_, px, src| {
+            for (x, index) in src.iter().zip(order) {
+                px[*index] = *x;
+            }
+        });
+        std::borrow::Cow::Owned(dest)
+    }
+
+    /// Reinterpret the pixel data using another color with the same number of channels
+    fn with_color<D: Color>(self) -> Image<T, D> {
+        Image {
+            meta: self.meta.cast(),
+            data: self.data,
+        }
+    }
+
+    /// Extract a single channel
+    pub fn band(&self, c: usize) -> Result<Image<T, Gray>, Error> {
+        self.select_bands(&[c])
+    }
+
+    /// Create a new image from the given channels, `bands` should contain one index for each
+    /// channel in the destination color. Returns `Error::InvalidBandCount` when it doesn't
+    pub fn select_bands<D: Color>(&self, bands: &[usize]) -> Result<Image<T, D>, Error> {
+        if bands.len() != D::CHANNELS {
+            return Err(Error::InvalidBandCount(D::CHANNELS, bands.len()));
+        }
+
+        if let Some(c) = bands.iter().find(|c| **c >= C::CHANNELS) {
+            return Err(Error::InvalidChannel(*c));
+        }
+
+        let mut dest = self.new_like_with_color::<D>();
+        dest.for_each(|(x, y), px| {
+            let src = self.get(x, y);
+            for (d, c) in px.iter_mut().zip(bands) {
+                *d = src[*c];
+            }
+        });
+        Ok(dest)
+    }
+
+    /// Create a new image by merging single channel images, one for each channel. Returns
+    /// `Error::InvalidBandCount` when the number of images doesn't match the color and
+    /// `Error::InvalidDimensions` when their sizes differ
+    pub fn merge_bands(bands: &[&Image<T, Gray>]) -> Result<Image<T, C>, Error> {
+        if bands.is_empty() || bands.len() != C::CHANNELS {
+            return Err(Error::InvalidBandCount(C::CHANNELS, bands.len()));
+        }
+
+        let (width, height) = (bands[0].width(), bands[0].height());
+        if let Some(band) = bands
+            .iter()
+            .find(|band| band.width() != width || band.height() != height)
+        {
+            return Err(Error::InvalidDimensions(band.width(), band.height(), 1));
+        }
+
+        let mut dest = bands[0].new_like_with_color::<C>();
+        dest.for_each(|(x, y), px| {
+            for (d, band) in px.iter_mut().zip(bands) {
+                *d = band.get(x, y)[0];
+            }
+        });
+        Ok(dest)
+    }
+
     /// Convert to `ImageBuf`
     #[cfg(feature = "oiio")]
     pub(crate) fn image_buf(&mut self) -> io::internal::ImageBuf {
@@ -691,27 +1285,56 @@ impl<T: Type, C: Color> Image<T, C> {
     }
 
     /// Convert colorspace from `a` to `b` into an existing image
-    #[cfg(feature = "oiio")]
+    ///
+    /// Colorspaces found in `colorspace::COLORSPACES` are converted without any external
+    /// library, other names are passed to OpenImageIO when the `oiio` feature is enabled and
+    /// result in `Error::UnknownColorspace` otherwise
     pub fn convert_colorspace_to(
         &self,
         dest: &mut Image<T, C>,
         a: impl AsRef<str>,
         b: impl AsRef<str>,
     ) -> Result<(), Error> {
-        let buf = self.const_image_buf();
-        let ok = buf.convert_color(&mut dest.image_buf(), a.as_ref(), b.as_ref());
-        if ok {
-            Ok(())
-        } else {
-            Err(Error::FailedColorConversion(
-                a.as_ref().into(),
-                b.as_ref().into(),
-            ))
+        let (a, b) = (a.as_ref(), b.as_ref());
+
+        #[cfg(feature = "oiio")]
+        {
+            use colorspace::Colorspace;
+            if Colorspace::find(a).is_none() || Colorspace::find(b).is_none() {
+                let buf = self.const_image_buf();
+                if buf.convert_color(&mut dest.image_buf(), a, b) {
+                    return Ok(());
+                }
+                return Err(Error::FailedColorConversion(a.into(), b.into()));
+            }
         }
+
+        let conversion = colorspace::Conversion::named(a, b)?;
+
+        // Color channels in RGB order, alpha is copied unchanged
+        let alpha = self.meta.alpha_channel();
+        let mut channels: Vec<usize> = (0..C::CHANNELS).filter(|c| Some(*c) != alpha).collect();
+        if !C::CHANNEL_ORDER.is_empty() {
+            channels.sort_by_key(|c| C::CHANNEL_ORDER[*c]);
+        }
+
+        dest.for_each2(self, |_, out, px| {
+            out.copy_from_slice(px);
+            if let [r, g, b, ..] = channels[..] {
+                let rgb = conversion.convert([px[r].to_norm(), px[g].to_norm(), px[b].to_norm()]);
+                out[r] = T::from_norm(rgb[0]);
+                out[g] = T::from_norm(rgb[1]);
+                out[b] = T::from_norm(rgb[2]);
+            } else {
+                for c in &channels {
+                    out[*c] = T::from_norm(conversion.convert_value(px[*c].to_norm()));
+                }
+            }
+        });
+        Ok(())
     }
 
     /// Convert colorspace from `a` to `b` into a new image
-    #[cfg(feature = "oiio")]
     pub fn convert_colorspace(
         &self,
         a: impl AsRef<str>,
@@ -724,53 +1347,194 @@ impl<T: Type, C: Color> Image<T, C> {
 
     /// Get image histogram
     pub fn histogram(&self, bins: usize) -> Vec<Histogram> {
-        let mut hist = vec![Histogram::new(bins); C::CHANNELS];
-
-        self.each_pixel(|_, px| {
-            for i in 0..C::CHANNELS {
-                hist[i].add_value(px[i]);
-            }
-        });
-
-        hist
+        self.reduce(&reduce::ComputeHistogram { bins })
     }
 
-    /// Gamma correction
-    pub fn gamma(&mut self, value: f64) {
+    /// Apply `f` to the normalized value of each color channel, skipping alpha
+    pub(crate) fn map_color_channels(&mut self, f: impl Sync + Send + Fn(f64) -> f64) {
+        let alpha = self.meta.alpha_channel();
         self.for_each(|_, px| {
-            for x in px {
-                *x = T::from_f64(T::to_f64(x).powf(1. / value))
+            for (c, x) in px.iter_mut().enumerate() {
+                if Some(c) != alpha {
+                    *x = T::from_norm(f(x.to_norm()));
+                }
             }
         })
     }
 
+    /// Gamma correction, normalized color channels are raised to `1 / value`
+    pub fn gamma(&mut self, value: f64) {
+        self.map_color_channels(|x| x.powf(1. / value))
+    }
+
+    /// Decode color channels encoded with `transfer` to linear light
+    pub fn linearize(&mut self, transfer: TransferFunction) {
+        self.map_color_channels(|x| transfer.decode(x))
+    }
+
+    /// Encode linear light color channels using `transfer`
+    pub fn delinearize(&mut self, transfer: TransferFunction) {
+        self.map_color_channels(|x| transfer.encode(x))
+    }
+
+    /// Find the smallest pixel, pixels are compared lexicographically. See `Image::stats` for
+    /// per-channel values
     pub fn min(&self) -> ((usize, usize), Pixel<C>) {
-        let mut min = Pixel::new();
-        let mut x = 0;
-        let mut y = 0;
-        self.iter().for_each(|((a, b), px)| {
-            let px = Pixel::from_slice(px);
-            if px < min {
-                min = px;
-                x = a;
-                y = b;
-            }
-        });
-        ((x, y), min)
+        self.find_pixel(|px, min| px < min)
     }
 
+    /// Find the largest pixel, pixels are compared lexicographically. See `Image::stats` for
+    /// per-channel values
     pub fn max(&self) -> ((usize, usize), Pixel<C>) {
-        let mut max = Pixel::new().fill(1.0);
-        let mut x = 0;
-        let mut y = 0;
-        self.iter().for_each(|((a, b), px)| {
+        self.find_pixel(|px, max| px > max)
+    }
+
+    fn find_pixel(
+        &self,
+        better: impl Fn(&Pixel<C>, &Pixel<C>) -> bool,
+    ) -> ((usize, usize), Pixel<C>) {
+        let mut best: Option<((usize, usize), Pixel<C>)> = None;
+        self.iter().for_each(|(pos, px)| {
             let px = Pixel::from_slice(px);
-            if px > max {
-                max = px;
-                x = a;
-                y = b;
+            let replace = match &best {
+                Some((_, b)) => better(&px, b),
+                None => true,
+            };
+            if replace {
+                best = Some((pos, px));
             }
         });
-        ((x, y), max)
+        best.unwrap_or_else(|| ((0, 0), Pixel::new()))
+    }
+
+    /// Get per-channel statistics for the whole image
+    pub fn stats(&self) -> Vec<Stats> {
+        self.stats_region(Region::new(0, 0, self.width(), self.height()))
+    }
+
+    /// Get per-channel statistics for the pixels inside `roi`
+    pub fn stats_region(&self, roi: Region) -> Vec<Stats> {
+        self.reduce_region(roi, &reduce::ComputeStats::default())
+            .into_iter()
+            .map(StatsAccumulator::finish)
+            .collect()
+    }
+
+    /// Get per-channel statistics for the pixels where `mask` is set, `mask` must be the same
+    /// size as the image
+    pub fn stats_masked(&self, mask: &Bitmap) -> Vec<Stats> {
+        assert_eq!(
+            (self.width(), self.height()),
+            mask.shape(),
+            "Mask dimensions don't match"
+        );
+        self.reduce(&reduce::ComputeStats { mask: Some(mask) })
+            .into_iter()
+            .map(StatsAccumulator::finish)
+            .collect()
+    }
+
+    /// Run a reduction over every pixel
+    pub fn reduce<R: Reduce<T, C>>(&self, reduce: &R) -> R::Output {
+        self.reduce_region(Region::new(0, 0, self.width(), self.height()), reduce)
+    }
+
+    /// Run a reduction over the pixels inside `roi`, in parallel when the `parallel` feature is
+    /// enabled
+    pub fn reduce_region<R: Reduce<T, C>>(&self, roi: Region, reduce: &R) -> R::Output {
+        let channels = C::CHANNELS;
+        let (x0, x1) = (
+            roi.x.min(self.width()),
+            (roi.x + roi.width).min(self.width()),
+        );
+        let (y0, y1) = (
+            roi.y.min(self.height()),
+            (roi.y + roi.height).min(self.height()),
+        );
+        let add_row = |mut acc: R::Output, y: usize| {
+            let row = self.row(y);
+            for x in x0..x1 {
+                reduce.add(&mut acc, x, y, &row[x * channels..(x + 1) * channels]);
+            }
+            acc
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            (y0..y1)
+                .into_par_iter()
+                .fold(|| reduce.init(), add_row)
+                .reduce(|| reduce.init(), |a, b| reduce.merge(a, b))
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        {
+            (y0..y1).fold(reduce.init(), add_row)
+        }
+    }
+
+    /// Contrast-limited adaptive histogram equalization of the image lightness, see
+    /// `filter::Clahe`. RGB images are converted to `Lab` and back, leaving alpha unchanged. For
+    /// every other color the first channel is equalized directly and the rest are kept
+    pub fn clahe(&self, tiles: (usize, usize), clip_limit: f64) -> Image<T, C> {
+        // Colors such as `Cmyk` or `Channels` with more than 3 bands don't survive a round trip
+        // through `Lab`, so only colors storing RGB are converted
+        let rgb = matches!(
+            C::NAME,
+            "rgb"
+                | "srgb"
+                | "linear_rgb"
+                | "rec2020"
+                | "rec2100_pq"
+                | "rec2100_hlg"
+                | "rgba"
+                | "premultiplied_rgba"
+                | "bgr"
+                | "bgra"
+                | "argb"
+        );
+        if !rgb {
+            let mut dest = self.new_like();
+            filter::Clahe::new(self, tiles, clip_limit).eval(&mut dest, &[self]);
+            return dest;
+        }
+
+        let lab: Image<f32, Lab> = self.convert();
+        let mut equalized = lab.new_like();
+        filter::Clahe::new(&lab, tiles, clip_limit).eval(&mut equalized, &[&lab]);
+
+        let mut dest: Image<T, C> = equalized.convert();
+        dest.meta = self.meta.clone();
+        if let Some(alpha) = self.meta.alpha_channel() {
+            dest.for_each2(self, |_, a, b| a[alpha] = b[alpha]);
+        }
+        dest
+    }
+
+    /// Get the `p`th percentile (`0..=100`) of each channel, interpolating linearly between
+    /// values. NaN values are ignored
+    pub fn percentile(&self, p: f64) -> Pixel<C> {
+        let mut px = Pixel::new();
+        let mut values = Vec::with_capacity(self.width() * self.height());
+        for c in 0..C::CHANNELS {
+            values.clear();
+            values.extend(
+                self.data
+                    .iter()
+                    .skip(c)
+                    .step_by(C::CHANNELS)
+                    .map(|x| x.to_norm())
+                    .filter(|x| !x.is_nan()),
+            );
+            if values.is_empty() {
+                continue;
+            }
+
+            values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            let rank = (p / 100.0).clamp(0.0, 1.0) * (values.len() - 1) as f64;
+            let (lo, hi) = (values[rank.floor() as usize], values[rank.ceil() as usize]);
+            px[c] = lo + (hi - lo) * rank.fract();
+        }
+        px
     }
 }
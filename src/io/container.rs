@@ -0,0 +1,123 @@
+//! Helpers for locating metadata inside of common image file containers
+
+use super::exif::Tiff;
+use crate::*;
+
+pub(crate) const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// Iterate over the marker segments of a JPEG file up to the start of scan
+pub(crate) fn jpeg_segments(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut offset = 2;
+    std::iter::from_fn(move || {
+        while data.get(offset) == Some(&0xff) && data.get(offset + 1) == Some(&0xff) {
+            offset += 1;
+        }
+
+        if data.get(offset) != Some(&0xff) {
+            return None;
+        }
+
+        let marker = *data.get(offset + 1)?;
+        if marker == 0xda || marker == 0xd9 {
+            return None;
+        }
+
+        let len = u16::from_be_bytes([*data.get(offset + 2)?, *data.get(offset + 3)?]) as usize;
+        let segment = data.get(offset + 4..offset + 2 + len.max(2))?;
+        offset += 2 + len;
+        Some((marker, segment))
+    })
+}
+
+/// Iterate over the chunks of a PNG file
+pub(crate) fn png_chunks(data: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut offset = PNG_SIGNATURE.len();
+    std::iter::from_fn(move || {
+        let len = data.get(offset..offset + 4)?;
+        let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        let name = data.get(offset + 4..offset + 8)?;
+        let chunk = data.get(offset + 8..offset + 8 + len)?;
+        offset += 12 + len;
+        Some((name, chunk))
+    })
+}
+
+/// Iterate over the chunks of a RIFF file
+pub(crate) fn riff_chunks(data: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut offset = 12;
+    std::iter::from_fn(move || {
+        let name = data.get(offset..offset + 4)?;
+        let len = data.get(offset + 4..offset + 8)?;
+        let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        let chunk = data.get(offset + 8..offset + 8 + len)?;
+        offset += 8 + len + (len & 1);
+        Some((name, chunk))
+    })
+}
+
+const ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+const TAG_ICC_PROFILE: u16 = 0x8773;
+
+/// Extract an embedded ICC profile from the contents of a JPEG, TIFF, PNG or WebP file
+pub(crate) fn icc_profile(data: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+    if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        let (tiff, offset) = Tiff::new(data)?;
+        let (entries, _) = tiff.ifd(offset)?;
+        return match entries.iter().find(|e| e.tag == TAG_ICC_PROFILE) {
+            Some(entry) => Ok(Some(tiff.value(entry)?.to_vec())),
+            None => Ok(None),
+        };
+    }
+
+    if data.starts_with(&[0xff, 0xd8]) {
+        // Profiles larger than a single segment are split into numbered chunks
+        let mut chunks: Vec<(u8, &[u8])> = jpeg_segments(data)
+            .filter(|(marker, segment)| {
+                *marker == 0xe2
+                    && segment.len() > ICC_HEADER.len() + 2
+                    && segment.starts_with(ICC_HEADER)
+            })
+            .map(|(_, segment)| (segment[ICC_HEADER.len()], &segment[ICC_HEADER.len() + 2..]))
+            .collect();
+
+        if chunks.is_empty() {
+            return Ok(None);
+        }
+
+        chunks.sort_by_key(|(index, _)| *index);
+        return Ok(Some(
+            chunks.iter().flat_map(|(_, c)| c.iter().copied()).collect(),
+        ));
+    }
+
+    if data.starts_with(PNG_SIGNATURE) {
+        let chunk = match png_chunks(data).find(|(name, _)| name == b"iCCP") {
+            Some((_, chunk)) => chunk,
+            None => return Ok(None),
+        };
+
+        // Profile name, null separator and compression method precede the zlib stream
+        let start = match chunk.iter().position(|c| *c == 0) {
+            Some(n) => n + 2,
+            None => return Err(Error::InvalidIccProfile("invalid iCCP chunk".into())),
+        };
+
+        return match chunk
+            .get(start..)
+            .map(miniz_oxide::inflate::decompress_to_vec_zlib)
+        {
+            Some(Ok(profile)) => Ok(Some(profile)),
+            _ => Err(Error::InvalidIccProfile(
+                "unable to decompress iCCP chunk".into(),
+            )),
+        };
+    }
+
+    if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return Ok(riff_chunks(data)
+            .find(|(name, _)| name == b"ICCP")
+            .map(|(_, chunk)| chunk.to_vec()));
+    }
+
+    Ok(None)
+}
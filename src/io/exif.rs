@@ -0,0 +1,606 @@
+//! Pure-Rust EXIF parsing
+//!
+//! EXIF data is stored as a TIFF structure, which can be embedded in JPEG (`APP1`), PNG (`eXIf`)
+//! and WebP (`EXIF`) files or found directly in the IFDs of a TIFF file.
+
+use std::path::Path;
+
+use super::container::{jpeg_segments, png_chunks, riff_chunks, PNG_SIGNATURE};
+use crate::*;
+
+/// Unsigned EXIF rational value
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Default)]
+pub struct Rational {
+    pub num: u32,
+    pub den: u32,
+}
+
+impl Rational {
+    /// Create a new rational value
+    pub fn new(num: u32, den: u32) -> Rational {
+        Rational { num, den }
+    }
+
+    /// Convert to floating point, a zero denominator results in `0.0`
+    pub fn to_f64(&self) -> f64 {
+        if self.den == 0 {
+            return 0.0;
+        }
+
+        self.num as f64 / self.den as f64
+    }
+
+    /// Get the closest rational value that fits in a `u32` numerator and denominator, negative
+    /// and NaN values result in `0/1`
+    pub fn from_f64(x: f64) -> Rational {
+        if x.is_nan() || x <= 0.0 {
+            return Rational::new(0, 1);
+        } else if x >= u32::MAX as f64 {
+            return Rational::new(u32::MAX, 1);
+        }
+
+        // Continued fraction expansion, stopping before the convergents overflow
+        let max = u32::MAX as u64;
+        let (mut h0, mut h1, mut k0, mut k1) = (0u64, 1u64, 1u64, 0u64);
+        let mut r = x;
+        loop {
+            let a = r.floor();
+            let (h, k) = (a as u64 * h1 + h0, a as u64 * k1 + k0);
+            if h > max || k > max {
+                break;
+            }
+
+            (h0, h1, k0, k1) = (h1, h, k1, k);
+            let frac = r - a;
+            if frac < 1e-9 || (h as f64 / k as f64 - x).abs() <= x * f64::EPSILON {
+                break;
+            }
+            r = 1.0 / frac;
+        }
+        Rational::new(h1 as u32, k1 as u32)
+    }
+}
+
+/// EXIF orientation, describes the transformation needed to display an image correctly
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, Default)]
+pub enum Orientation {
+    #[default]
+    Normal = 1,
+    FlipHorizontal = 2,
+    Rotate180 = 3,
+    FlipVertical = 4,
+    Transpose = 5,
+    Rotate90 = 6,
+    Transverse = 7,
+    Rotate270 = 8,
+}
+
+impl Orientation {
+    /// Get orientation from the value of the EXIF orientation tag
+    pub fn from_u16(x: u16) -> Option<Orientation> {
+        use Orientation::*;
+        let o = match x {
+            1 => Normal,
+            2 => FlipHorizontal,
+            3 => Rotate180,
+            4 => FlipVertical,
+            5 => Transpose,
+            6 => Rotate90,
+            7 => Transverse,
+            8 => Rotate270,
+            _ => return None,
+        };
+        Some(o)
+    }
+
+    /// Returns true when applying the orientation swaps width and height
+    pub fn swaps_dimensions(&self) -> bool {
+        use Orientation::*;
+        matches!(self, Transpose | Rotate90 | Transverse | Rotate270)
+    }
+}
+
+/// GPS position
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Default)]
+pub struct Gps {
+    /// Degrees, minutes and seconds
+    pub latitude: [Rational; 3],
+
+    /// `N` or `S`
+    pub latitude_ref: Option<char>,
+
+    /// Degrees, minutes and seconds
+    pub longitude: [Rational; 3],
+
+    /// `E` or `W`
+    pub longitude_ref: Option<char>,
+
+    /// Altitude in meters
+    pub altitude: Option<Rational>,
+
+    /// `0` for above sea level, `1` for below
+    pub altitude_ref: u8,
+}
+
+fn degrees(x: &[Rational; 3]) -> f64 {
+    x[0].to_f64() + x[1].to_f64() / 60.0 + x[2].to_f64() / 3600.0
+}
+
+impl Gps {
+    /// Latitude in decimal degrees, negative values are south of the equator
+    pub fn latitude(&self) -> f64 {
+        let d = degrees(&self.latitude);
+        if self.latitude_ref == Some('S') {
+            -d
+        } else {
+            d
+        }
+    }
+
+    /// Longitude in decimal degrees, negative values are west of the prime meridian
+    pub fn longitude(&self) -> f64 {
+        let d = degrees(&self.longitude);
+        if self.longitude_ref == Some('W') {
+            -d
+        } else {
+            d
+        }
+    }
+
+    /// Altitude in meters, negative values are below sea level
+    pub fn altitude(&self) -> Option<f64> {
+        let a = self.altitude?.to_f64();
+        if self.altitude_ref == 1 {
+            Some(-a)
+        } else {
+            Some(a)
+        }
+    }
+}
+
+/// EXIF metadata
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Default)]
+pub struct Exif {
+    pub orientation: Orientation,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub lens_model: Option<String>,
+    pub software: Option<String>,
+
+    /// Exposure time in seconds
+    pub exposure_time: Option<Rational>,
+    pub f_number: Option<Rational>,
+    pub iso: Option<u32>,
+
+    /// Focal length in millimeters
+    pub focal_length: Option<Rational>,
+
+    /// Modification time, formatted as `YYYY:MM:DD HH:MM:SS`
+    pub datetime: Option<String>,
+
+    /// Capture time, formatted as `YYYY:MM:DD HH:MM:SS`
+    pub datetime_original: Option<String>,
+    pub gps: Option<Gps>,
+}
+
+const TAG_MAKE: u16 = 0x010f;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_SOFTWARE: u16 = 0x0131;
+const TAG_DATETIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+const TAG_EXPOSURE_TIME: u16 = 0x829a;
+const TAG_F_NUMBER: u16 = 0x829d;
+const TAG_ISO: u16 = 0x8827;
+const TAG_DATETIME_ORIGINAL: u16 = 0x9003;
+const TAG_FOCAL_LENGTH: u16 = 0x920a;
+const TAG_LENS_MODEL: u16 = 0xa434;
+const TAG_GPS_LATITUDE_REF: u16 = 0x0001;
+const TAG_GPS_LATITUDE: u16 = 0x0002;
+const TAG_GPS_LONGITUDE_REF: u16 = 0x0003;
+const TAG_GPS_LONGITUDE: u16 = 0x0004;
+const TAG_GPS_ALTITUDE_REF: u16 = 0x0005;
+const TAG_GPS_ALTITUDE: u16 = 0x0006;
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+fn invalid(s: &str) -> Error {
+    Error::InvalidExif(s.into())
+}
+
+/// Byte-order aware reader for TIFF structures
+#[derive(Clone, Copy)]
+pub(crate) struct Tiff<'a> {
+    pub data: &'a [u8],
+    pub little_endian: bool,
+}
+
+/// A single IFD entry
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Entry {
+    pub tag: u16,
+    pub kind: u16,
+    pub count: u32,
+
+    /// Offset of the 4-byte value/offset field
+    pub field: usize,
+}
+
+impl<'a> Tiff<'a> {
+    /// Parse a TIFF header, returning the reader and the offset of the first IFD
+    pub fn new(data: &'a [u8]) -> Result<(Tiff<'a>, usize), Error> {
+        let little_endian = match data.get(0..4) {
+            Some(b"II*\0") => true,
+            Some(b"MM\0*") => false,
+            _ => return Err(invalid("missing TIFF header")),
+        };
+        let tiff = Tiff {
+            data,
+            little_endian,
+        };
+        let offset = tiff.u32(4)? as usize;
+        Ok((tiff, offset))
+    }
+
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'a [u8], Error> {
+        offset
+            .checked_add(len)
+            .and_then(|end| self.data.get(offset..end))
+            .ok_or_else(|| invalid("offset out of bounds"))
+    }
+
+    pub fn u16(&self, offset: usize) -> Result<u16, Error> {
+        let b = self.bytes(offset, 2)?;
+        let b = [b[0], b[1]];
+        Ok(if self.little_endian {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    }
+
+    pub fn u32(&self, offset: usize) -> Result<u32, Error> {
+        let b = self.bytes(offset, 4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Ok(if self.little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    /// Read the entries of the IFD at `offset`, returns the entries and the offset of the next IFD
+    pub fn ifd(&self, offset: usize) -> Result<(Vec<Entry>, usize), Error> {
+        let n = self.u16(offset)? as usize;
+        let mut entries = Vec::with_capacity(n);
+        for i in 0..n {
+            let start = offset + 2 + i * 12;
+            entries.push(Entry {
+                tag: self.u16(start)?,
+                kind: self.u16(start + 2)?,
+                count: self.u32(start + 4)?,
+                field: start + 8,
+            });
+        }
+        let next = self.u32(offset + 2 + n * 12)? as usize;
+        Ok((entries, next))
+    }
+
+    /// Get the offset and length of the data referenced by an entry
+    pub fn value_offset(&self, entry: &Entry) -> Result<(usize, usize), Error> {
+        let size = match entry.kind {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 => 8,
+            _ => return Err(invalid("unknown field type")),
+        };
+        let len = (entry.count as usize)
+            .checked_mul(size)
+            .ok_or_else(|| invalid("field too large"))?;
+        if len <= 4 {
+            Ok((entry.field, len))
+        } else {
+            Ok((self.u32(entry.field)? as usize, len))
+        }
+    }
+
+    /// Get the raw bytes referenced by an entry
+    pub fn value(&self, entry: &Entry) -> Result<&'a [u8], Error> {
+        let (offset, len) = self.value_offset(entry)?;
+        self.bytes(offset, len)
+    }
+
+    /// Read the `index`th integer value of a BYTE, SHORT or LONG entry
+    pub fn uint(&self, entry: &Entry, index: usize) -> Result<u32, Error> {
+        if index >= entry.count as usize {
+            return Err(invalid("index out of bounds"));
+        }
+
+        let (offset, _) = self.value_offset(entry)?;
+        match entry.kind {
+            1 | 7 => Ok(self.bytes(offset + index, 1)?[0] as u32),
+            3 => Ok(self.u16(offset + index * 2)? as u32),
+            4 => self.u32(offset + index * 4),
+            _ => Err(invalid("expected integer field")),
+        }
+    }
+
+    fn rational(&self, entry: &Entry, index: usize) -> Result<Rational, Error> {
+        if entry.kind != 5 || index >= entry.count as usize {
+            return Err(invalid("expected rational field"));
+        }
+        let (offset, _) = self.value_offset(entry)?;
+        let offset = offset + index * 8;
+        Ok(Rational::new(self.u32(offset)?, self.u32(offset + 4)?))
+    }
+
+    fn string(&self, entry: &Entry) -> Result<String, Error> {
+        if entry.kind != 2 {
+            return Err(invalid("expected string field"));
+        }
+        let s = self.value(entry)?;
+        let s = s.split(|c| *c == 0).next().unwrap_or_default();
+        Ok(String::from_utf8_lossy(s).trim_end().to_string())
+    }
+}
+
+impl Exif {
+    /// Parse EXIF data from a TIFF structure
+    pub fn parse(data: &[u8]) -> Result<Exif, Error> {
+        let (tiff, offset) = Tiff::new(data)?;
+        let (entries, _) = tiff.ifd(offset)?;
+        let mut exif = Exif::default();
+
+        for entry in entries.iter() {
+            match entry.tag {
+                TAG_ORIENTATION => {
+                    exif.orientation =
+                        Orientation::from_u16(tiff.uint(entry, 0)? as u16).unwrap_or_default()
+                }
+                TAG_MAKE => exif.make = tiff.string(entry).ok(),
+                TAG_MODEL => exif.model = tiff.string(entry).ok(),
+                TAG_SOFTWARE => exif.software = tiff.string(entry).ok(),
+                TAG_DATETIME => exif.datetime = tiff.string(entry).ok(),
+                TAG_EXIF_IFD => exif.parse_exif_ifd(&tiff, tiff.uint(entry, 0)? as usize)?,
+                TAG_GPS_IFD => {
+                    exif.gps = Some(parse_gps_ifd(&tiff, tiff.uint(entry, 0)? as usize)?)
+                }
+                _ => (),
+            }
+        }
+
+        Ok(exif)
+    }
+
+    fn parse_exif_ifd(&mut self, tiff: &Tiff, offset: usize) -> Result<(), Error> {
+        let (entries, _) = tiff.ifd(offset)?;
+        for entry in entries.iter() {
+            match entry.tag {
+                TAG_EXPOSURE_TIME => self.exposure_time = tiff.rational(entry, 0).ok(),
+                TAG_F_NUMBER => self.f_number = tiff.rational(entry, 0).ok(),
+                TAG_ISO => self.iso = tiff.uint(entry, 0).ok(),
+                TAG_FOCAL_LENGTH => self.focal_length = tiff.rational(entry, 0).ok(),
+                TAG_DATETIME_ORIGINAL => self.datetime_original = tiff.string(entry).ok(),
+                TAG_LENS_MODEL => self.lens_model = tiff.string(entry).ok(),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Extract EXIF data from the contents of a JPEG, TIFF, PNG or WebP file
+    ///
+    /// Returns `Ok(None)` when the container is recognized but has no EXIF data
+    pub fn from_bytes(data: &[u8]) -> Result<Option<Exif>, Error> {
+        match find(data)? {
+            Some(tiff) => Exif::parse(tiff).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Read EXIF data from a file on disk
+    pub fn read(path: impl AsRef<Path>) -> Result<Option<Exif>, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .map_err(|_| Error::UnableToOpenImage(path.to_string_lossy().to_string()))?;
+        Exif::from_bytes(&data)
+    }
+}
+
+fn parse_gps_ifd(tiff: &Tiff, offset: usize) -> Result<Gps, Error> {
+    let (entries, _) = tiff.ifd(offset)?;
+    let mut gps = Gps::default();
+    let dms = |entry: &Entry| -> Result<[Rational; 3], Error> {
+        Ok([
+            tiff.rational(entry, 0)?,
+            tiff.rational(entry, 1)?,
+            tiff.rational(entry, 2)?,
+        ])
+    };
+    let reference = |entry: &Entry| tiff.string(entry).ok().and_then(|s| s.chars().next());
+
+    for entry in entries.iter() {
+        match entry.tag {
+            TAG_GPS_LATITUDE_REF => gps.latitude_ref = reference(entry),
+            TAG_GPS_LATITUDE => gps.latitude = dms(entry)?,
+            TAG_GPS_LONGITUDE_REF => gps.longitude_ref = reference(entry),
+            TAG_GPS_LONGITUDE => gps.longitude = dms(entry)?,
+            TAG_GPS_ALTITUDE_REF => gps.altitude_ref = tiff.uint(entry, 0)? as u8,
+            TAG_GPS_ALTITUDE => gps.altitude = tiff.rational(entry, 0).ok(),
+            _ => (),
+        }
+    }
+
+    Ok(gps)
+}
+
+fn strip_exif_header(data: &[u8]) -> &[u8] {
+    if data.starts_with(EXIF_HEADER) {
+        &data[EXIF_HEADER.len()..]
+    } else {
+        data
+    }
+}
+
+/// Locate the TIFF structure containing EXIF data inside of an image file
+fn find(data: &[u8]) -> Result<Option<&[u8]>, Error> {
+    if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        return Ok(Some(data));
+    }
+
+    if data.starts_with(&[0xff, 0xd8]) {
+        return Ok(jpeg_segments(data)
+            .find(|(marker, segment)| *marker == 0xe1 && segment.starts_with(EXIF_HEADER))
+            .map(|(_, segment)| &segment[EXIF_HEADER.len()..]));
+    }
+
+    if data.starts_with(PNG_SIGNATURE) {
+        return Ok(png_chunks(data)
+            .find(|(name, _)| name == b"eXIf")
+            .map(|(_, chunk)| strip_exif_header(chunk)));
+    }
+
+    if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return Ok(riff_chunks(data)
+            .find(|(name, _)| name == b"EXIF")
+            .map(|(_, chunk)| strip_exif_header(chunk)));
+    }
+
+    Err(invalid("unsupported container"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a minimal EXIF TIFF structure with orientation, make, exposure time and GPS latitude
+    fn tiff(little_endian: bool) -> Vec<u8> {
+        let u16b = |x: u16| {
+            if little_endian {
+                x.to_le_bytes()
+            } else {
+                x.to_be_bytes()
+            }
+        };
+        let u32b = |x: u32| {
+            if little_endian {
+                x.to_le_bytes()
+            } else {
+                x.to_be_bytes()
+            }
+        };
+
+        let mut d = Vec::new();
+        d.extend_from_slice(if little_endian { b"II*\0" } else { b"MM\0*" });
+        d.extend_from_slice(&u32b(8));
+
+        // IFD0 at 8: 4 entries, ends at 8 + 2 + 48 + 4 = 62
+        d.extend_from_slice(&u16b(4));
+        d.extend_from_slice(&u16b(TAG_MAKE));
+        d.extend_from_slice(&u16b(2));
+        d.extend_from_slice(&u32b(4));
+        d.extend_from_slice(b"ACM\0");
+        d.extend_from_slice(&u16b(TAG_ORIENTATION));
+        d.extend_from_slice(&u16b(3));
+        d.extend_from_slice(&u32b(1));
+        d.extend_from_slice(&u16b(6));
+        d.extend_from_slice(&u16b(0));
+        d.extend_from_slice(&u16b(TAG_EXIF_IFD));
+        d.extend_from_slice(&u16b(4));
+        d.extend_from_slice(&u32b(1));
+        d.extend_from_slice(&u32b(62));
+        d.extend_from_slice(&u16b(TAG_GPS_IFD));
+        d.extend_from_slice(&u16b(4));
+        d.extend_from_slice(&u32b(1));
+        d.extend_from_slice(&u32b(88));
+        d.extend_from_slice(&u32b(0));
+
+        // Exif IFD at 62: 1 entry, ends at 62 + 2 + 12 + 4 = 80, value at 80
+        d.extend_from_slice(&u16b(1));
+        d.extend_from_slice(&u16b(TAG_EXPOSURE_TIME));
+        d.extend_from_slice(&u16b(5));
+        d.extend_from_slice(&u32b(1));
+        d.extend_from_slice(&u32b(80));
+        d.extend_from_slice(&u32b(0));
+        d.extend_from_slice(&u32b(1));
+        d.extend_from_slice(&u32b(250));
+
+        // GPS IFD at 88: 2 entries, ends at 88 + 2 + 24 + 4 = 118, values at 118
+        d.extend_from_slice(&u16b(2));
+        d.extend_from_slice(&u16b(TAG_GPS_LATITUDE_REF));
+        d.extend_from_slice(&u16b(2));
+        d.extend_from_slice(&u32b(2));
+        d.extend_from_slice(b"S\0\0\0");
+        d.extend_from_slice(&u16b(TAG_GPS_LATITUDE));
+        d.extend_from_slice(&u16b(5));
+        d.extend_from_slice(&u32b(3));
+        d.extend_from_slice(&u32b(118));
+        d.extend_from_slice(&u32b(0));
+        for (n, dd) in &[(33, 1), (30, 1), (36, 1)] {
+            d.extend_from_slice(&u32b(*n));
+            d.extend_from_slice(&u32b(*dd));
+        }
+
+        d
+    }
+
+    fn check(exif: &Exif) {
+        assert_eq!(exif.orientation, Orientation::Rotate90);
+        assert_eq!(exif.make.as_deref(), Some("ACM"));
+        assert_eq!(exif.exposure_time, Some(Rational::new(1, 250)));
+        let gps = exif.gps.as_ref().unwrap();
+        assert!((gps.latitude() + 33.51).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rational() {
+        assert_eq!(Rational::from_f64(0.5), Rational::new(1, 2));
+        assert_eq!(Rational::from_f64(72.0), Rational::new(72, 1));
+        assert_eq!(Rational::from_f64(-1.0), Rational::new(0, 1));
+        assert_eq!(Rational::from_f64(f64::NAN), Rational::new(0, 1));
+        assert_eq!(Rational::from_f64(1e12), Rational::new(u32::MAX, 1));
+        for x in [118.11, 1.0 / 3.0, std::f64::consts::PI, 1e-6] {
+            assert!((Rational::from_f64(x).to_f64() - x).abs() < x * 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_exif_parse() {
+        check(&Exif::parse(&tiff(true)).unwrap());
+        check(&Exif::parse(&tiff(false)).unwrap());
+    }
+
+    #[test]
+    fn test_exif_containers() {
+        let tiff = tiff(false);
+
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, 0xff, 0xe1];
+        jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(EXIF_HEADER);
+        jpeg.extend_from_slice(&tiff);
+        jpeg.extend_from_slice(&[0xff, 0xda]);
+        check(&Exif::from_bytes(&jpeg).unwrap().unwrap());
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(&(tiff.len() as u32).to_be_bytes());
+        png.extend_from_slice(b"eXIf");
+        png.extend_from_slice(&tiff);
+        png.extend_from_slice(&[0; 4]);
+        check(&Exif::from_bytes(&png).unwrap().unwrap());
+
+        let mut webp = b"RIFF\0\0\0\0WEBP".to_vec();
+        webp.extend_from_slice(b"EXIF");
+        webp.extend_from_slice(&(tiff.len() as u32).to_le_bytes());
+        webp.extend_from_slice(&tiff);
+        check(&Exif::from_bytes(&webp).unwrap().unwrap());
+
+        assert!(Exif::from_bytes(&PNG_SIGNATURE).unwrap().is_none());
+        assert!(Exif::from_bytes(b"not an image").is_err());
+    }
+}
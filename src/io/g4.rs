@@ -0,0 +1,800 @@
+//! CCITT Group 4 (T.6) fax compression and bilevel TIFF files
+//!
+//! The codec works on bitmaps where set bits are black, matching the default `WhiteIsZero`
+//! photometric interpretation used by TIFF. `encode_tiff` and `decode_tiff` take care of
+//! converting to and from the `Bitmap` convention where set bits are white.
+
+use std::ops::Range;
+
+use super::exif::Tiff;
+use crate::*;
+
+/// A code as (number of bits, bits)
+type Code = (u8, u16);
+
+const PASS: Code = (4, 0b0001);
+const HORIZONTAL: Code = (3, 0b001);
+const EOL: Code = (12, 0b000000000001);
+
+/// Vertical mode codes for `a1 - b1` from -3 to 3
+const VERTICAL: [Code; 7] = [
+    (7, 0b0000010),
+    (6, 0b000010),
+    (3, 0b010),
+    (1, 0b1),
+    (3, 0b011),
+    (6, 0b000011),
+    (7, 0b0000011),
+];
+
+/// White terminating codes for runs of 0 to 63 pixels
+const WHITE_TERMINATING: [Code; 64] = [
+    (8, 0b00110101),
+    (6, 0b000111),
+    (4, 0b0111),
+    (4, 0b1000),
+    (4, 0b1011),
+    (4, 0b1100),
+    (4, 0b1110),
+    (4, 0b1111),
+    (5, 0b10011),
+    (5, 0b10100),
+    (5, 0b00111),
+    (5, 0b01000),
+    (6, 0b001000),
+    (6, 0b000011),
+    (6, 0b110100),
+    (6, 0b110101),
+    (6, 0b101010),
+    (6, 0b101011),
+    (7, 0b0100111),
+    (7, 0b0001100),
+    (7, 0b0001000),
+    (7, 0b0010111),
+    (7, 0b0000011),
+    (7, 0b0000100),
+    (7, 0b0101000),
+    (7, 0b0101011),
+    (7, 0b0010011),
+    (7, 0b0100100),
+    (7, 0b0011000),
+    (8, 0b00000010),
+    (8, 0b00000011),
+    (8, 0b00011010),
+    (8, 0b00011011),
+    (8, 0b00010010),
+    (8, 0b00010011),
+    (8, 0b00010100),
+    (8, 0b00010101),
+    (8, 0b00010110),
+    (8, 0b00010111),
+    (8, 0b00101000),
+    (8, 0b00101001),
+    (8, 0b00101010),
+    (8, 0b00101011),
+    (8, 0b00101100),
+    (8, 0b00101101),
+    (8, 0b00000100),
+    (8, 0b00000101),
+    (8, 0b00001010),
+    (8, 0b00001011),
+    (8, 0b01010010),
+    (8, 0b01010011),
+    (8, 0b01010100),
+    (8, 0b01010101),
+    (8, 0b00100100),
+    (8, 0b00100101),
+    (8, 0b01011000),
+    (8, 0b01011001),
+    (8, 0b01011010),
+    (8, 0b01011011),
+    (8, 0b01001010),
+    (8, 0b01001011),
+    (8, 0b00110010),
+    (8, 0b00110011),
+    (8, 0b00110100),
+];
+
+/// White makeup codes for runs of 64 to 1728 pixels
+const WHITE_MAKEUP: [Code; 27] = [
+    (5, 0b11011),
+    (5, 0b10010),
+    (6, 0b010111),
+    (7, 0b0110111),
+    (8, 0b00110110),
+    (8, 0b00110111),
+    (8, 0b01100100),
+    (8, 0b01100101),
+    (8, 0b01101000),
+    (8, 0b01100111),
+    (9, 0b011001100),
+    (9, 0b011001101),
+    (9, 0b011010010),
+    (9, 0b011010011),
+    (9, 0b011010100),
+    (9, 0b011010101),
+    (9, 0b011010110),
+    (9, 0b011010111),
+    (9, 0b011011000),
+    (9, 0b011011001),
+    (9, 0b011011010),
+    (9, 0b011011011),
+    (9, 0b010011000),
+    (9, 0b010011001),
+    (9, 0b010011010),
+    (6, 0b011000),
+    (9, 0b010011011),
+];
+
+/// Black terminating codes for runs of 0 to 63 pixels
+const BLACK_TERMINATING: [Code; 64] = [
+    (10, 0b0000110111),
+    (3, 0b010),
+    (2, 0b11),
+    (2, 0b10),
+    (3, 0b011),
+    (4, 0b0011),
+    (4, 0b0010),
+    (5, 0b00011),
+    (6, 0b000101),
+    (6, 0b000100),
+    (7, 0b0000100),
+    (7, 0b0000101),
+    (7, 0b0000111),
+    (8, 0b00000100),
+    (8, 0b00000111),
+    (9, 0b000011000),
+    (10, 0b0000010111),
+    (10, 0b0000011000),
+    (10, 0b0000001000),
+    (11, 0b00001100111),
+    (11, 0b00001101000),
+    (11, 0b00001101100),
+    (11, 0b00000110111),
+    (11, 0b00000101000),
+    (11, 0b00000010111),
+    (11, 0b00000011000),
+    (12, 0b000011001010),
+    (12, 0b000011001011),
+    (12, 0b000011001100),
+    (12, 0b000011001101),
+    (12, 0b000001101000),
+    (12, 0b000001101001),
+    (12, 0b000001101010),
+    (12, 0b000001101011),
+    (12, 0b000011010010),
+    (12, 0b000011010011),
+    (12, 0b000011010100),
+    (12, 0b000011010101),
+    (12, 0b000011010110),
+    (12, 0b000011010111),
+    (12, 0b000001101100),
+    (12, 0b000001101101),
+    (12, 0b000011011010),
+    (12, 0b000011011011),
+    (12, 0b000001010100),
+    (12, 0b000001010101),
+    (12, 0b000001010110),
+    (12, 0b000001010111),
+    (12, 0b000001100100),
+    (12, 0b000001100101),
+    (12, 0b000001010010),
+    (12, 0b000001010011),
+    (12, 0b000000100100),
+    (12, 0b000000110111),
+    (12, 0b000000111000),
+    (12, 0b000000100111),
+    (12, 0b000000101000),
+    (12, 0b000001011000),
+    (12, 0b000001011001),
+    (12, 0b000000101011),
+    (12, 0b000000101100),
+    (12, 0b000001011010),
+    (12, 0b000001100110),
+    (12, 0b000001100111),
+];
+
+/// Black makeup codes for runs of 64 to 1728 pixels
+const BLACK_MAKEUP: [Code; 27] = [
+    (10, 0b0000001111),
+    (12, 0b000011001000),
+    (12, 0b000011001001),
+    (12, 0b000001011011),
+    (12, 0b000000110011),
+    (12, 0b000000110100),
+    (12, 0b000000110101),
+    (13, 0b0000001101100),
+    (13, 0b0000001101101),
+    (13, 0b0000001001010),
+    (13, 0b0000001001011),
+    (13, 0b0000001001100),
+    (13, 0b0000001001101),
+    (13, 0b0000001110010),
+    (13, 0b0000001110011),
+    (13, 0b0000001110100),
+    (13, 0b0000001110101),
+    (13, 0b0000001110110),
+    (13, 0b0000001110111),
+    (13, 0b0000001010010),
+    (13, 0b0000001010011),
+    (13, 0b0000001010100),
+    (13, 0b0000001010101),
+    (13, 0b0000001011010),
+    (13, 0b0000001011011),
+    (13, 0b0000001100100),
+    (13, 0b0000001100101),
+];
+
+/// Makeup codes shared by both colors for runs of 1792 to 2560 pixels
+const EXTENDED_MAKEUP: [Code; 13] = [
+    (11, 0b00000001000),
+    (11, 0b00000001100),
+    (11, 0b00000001101),
+    (12, 0b000000010010),
+    (12, 0b000000010011),
+    (12, 0b000000010100),
+    (12, 0b000000010101),
+    (12, 0b000000010110),
+    (12, 0b000000010111),
+    (12, 0b000000011100),
+    (12, 0b000000011101),
+    (12, 0b000000011110),
+    (12, 0b000000011111),
+];
+
+/// Longest run code in bits
+const MAX_CODE_LEN: u32 = 13;
+
+/// Largest supported width or height
+const MAX_SIZE: usize = 1 << 20;
+
+fn invalid(s: &str) -> Error {
+    Error::CannotReadImage(format!("invalid CCITT G4 data: {}", s))
+}
+
+/// Check the size of an image before allocating it, every coded row takes at least one bit of
+/// `data`
+fn check_size(data: &[u8], width: usize, height: usize) -> Result<(), Error> {
+    if width > MAX_SIZE || height > MAX_SIZE || height > data.len().saturating_mul(8) {
+        return Err(invalid("image too large"));
+    }
+    Ok(())
+}
+
+fn tiff_error(e: Error) -> Error {
+    match e {
+        Error::InvalidExif(s) => Error::CannotReadImage(format!("invalid TIFF file: {}", s)),
+        e => e,
+    }
+}
+
+fn run_tables(black: bool) -> (&'static [Code; 64], &'static [Code; 27]) {
+    if black {
+        (&BLACK_TERMINATING, &BLACK_MAKEUP)
+    } else {
+        (&WHITE_TERMINATING, &WHITE_MAKEUP)
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    data: Vec<u8>,
+    acc: u32,
+    n: u8,
+}
+
+impl BitWriter {
+    fn put(&mut self, (len, code): Code) {
+        self.acc = (self.acc << len) | code as u32;
+        self.n += len;
+        while self.n >= 8 {
+            self.n -= 8;
+            self.data.push((self.acc >> self.n) as u8);
+        }
+        self.acc &= (1 << self.n) - 1;
+    }
+
+    fn put_run(&mut self, mut run: usize, black: bool) {
+        let (terminating, makeup) = run_tables(black);
+        while run >= 2560 + 64 {
+            self.put(EXTENDED_MAKEUP[12]);
+            run -= 2560;
+        }
+
+        if run >= 64 {
+            let m = run / 64;
+            self.put(if m <= 27 {
+                makeup[m - 1]
+            } else {
+                EXTENDED_MAKEUP[m - 28]
+            });
+            run -= m * 64;
+        }
+
+        self.put(terminating[run]);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.n > 0 {
+            self.data.push((self.acc << (8 - self.n)) as u8);
+        }
+        self.data
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// Get the next `n` bits without consuming them, reading past the end returns zeros
+    fn peek(&self, n: u32) -> u32 {
+        let i = self.pos / 8;
+        let mut word = [0; 4];
+        for (k, b) in word.iter_mut().enumerate() {
+            *b = self.data.get(i + k).copied().unwrap_or(0);
+        }
+        (u32::from_be_bytes(word) << (self.pos % 8)) >> (32 - n)
+    }
+
+    fn consume(&mut self, n: u32) -> Result<(), Error> {
+        self.pos += n as usize;
+        if self.pos > self.data.len() * 8 {
+            return Err(invalid("unexpected end of data"));
+        }
+        Ok(())
+    }
+
+    fn matches(&mut self, (len, code): Code) -> Result<bool, Error> {
+        if self.peek(len as u32) == code as u32 {
+            self.consume(len as u32)?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn run(&mut self, table: &[u32]) -> Result<usize, Error> {
+        let mut total = 0;
+        loop {
+            let entry = table[self.peek(MAX_CODE_LEN) as usize];
+            let len = entry >> 16;
+            if len == 0 {
+                return Err(invalid("unknown run length code"));
+            }
+            self.consume(len)?;
+
+            let run = (entry & 0xffff) as usize;
+            total += run;
+            if run < 64 {
+                return Ok(total);
+            }
+        }
+    }
+}
+
+/// Build a table mapping every `MAX_CODE_LEN` bit prefix to `(code length << 16) | run`
+fn decode_table(black: bool) -> Vec<u32> {
+    let (terminating, makeup) = run_tables(black);
+    let codes = terminating
+        .iter()
+        .enumerate()
+        .chain(makeup.iter().enumerate().map(|(i, c)| ((i + 1) * 64, c)))
+        .chain(
+            EXTENDED_MAKEUP
+                .iter()
+                .enumerate()
+                .map(|(i, c)| ((i + 28) * 64, c)),
+        );
+
+    let mut table = vec![0; 1 << MAX_CODE_LEN];
+    for (run, &(len, code)) in codes {
+        let shift = MAX_CODE_LEN - len as u32;
+        let start = (code as usize) << shift;
+        for entry in &mut table[start..start + (1 << shift)] {
+            *entry = (len as u32) << 16 | run as u32;
+        }
+    }
+    table
+}
+
+/// Positions where the color changes, starting from an imaginary white pixel before the row
+fn changes(row: &[u64], width: usize) -> Vec<usize> {
+    let mut changes = Vec::new();
+    let mut carry = 0;
+    for (i, &word) in row.iter().enumerate() {
+        let mut t = word ^ ((word << 1) | carry);
+        carry = word >> 63;
+        if i == width / 64 {
+            t &= (1 << (width % 64)) - 1;
+        }
+
+        while t != 0 {
+            changes.push(i * 64 + t.trailing_zeros() as usize);
+            t &= t - 1;
+        }
+    }
+    changes
+}
+
+/// Find `b1` and `b2`: the first change on the reference line after `a0` to the opposite of
+/// the current color, and the change following it
+fn reference(
+    changes: &[usize],
+    start: &mut usize,
+    a0: isize,
+    black: bool,
+    width: usize,
+) -> (usize, usize) {
+    while *start < changes.len() && changes[*start] as isize <= a0 {
+        *start += 1;
+    }
+
+    // Even entries are changes to black
+    let mut i = *start;
+    if (i % 2 == 1) != black {
+        i += 1;
+    }
+
+    (
+        changes.get(i).copied().unwrap_or(width),
+        changes.get(i + 1).copied().unwrap_or(width),
+    )
+}
+
+fn set_range(row: &mut [u64], mut start: usize, end: usize) {
+    while start < end {
+        let bit = start % 64;
+        let n = (64 - bit).min(end - start);
+        let mask = if n == 64 { !0 } else { ((1 << n) - 1) << bit };
+        row[start / 64] |= mask;
+        start += n;
+    }
+}
+
+/// Encode a bitmap where set bits are black, the result is terminated by an EOFB code
+pub fn encode(bitmap: &Bitmap) -> Vec<u8> {
+    let width = bitmap.width();
+    let mut writer = BitWriter::default();
+    let mut reference_line = Vec::new();
+
+    for y in 0..bitmap.height() {
+        let line = changes(bitmap.row(y), width);
+        let (mut a0, mut black) = (-1, false);
+        let (mut i, mut start) = (0, 0);
+
+        while a0 < width as isize {
+            while i < line.len() && line[i] as isize <= a0 {
+                i += 1;
+            }
+            let a1 = line.get(i).copied().unwrap_or(width);
+            let a2 = line.get(i + 1).copied().unwrap_or(width);
+            let (b1, b2) = reference(&reference_line, &mut start, a0, black, width);
+
+            let d = a1 as isize - b1 as isize;
+            if b2 < a1 {
+                writer.put(PASS);
+                a0 = b2 as isize;
+            } else if (-3..=3).contains(&d) {
+                writer.put(VERTICAL[(d + 3) as usize]);
+                a0 = a1 as isize;
+                black = !black;
+            } else {
+                writer.put(HORIZONTAL);
+                writer.put_run(a1 - a0.max(0) as usize, black);
+                writer.put_run(a2 - a1, !black);
+                a0 = a2 as isize;
+            }
+        }
+
+        reference_line = line;
+    }
+
+    writer.put(EOL);
+    writer.put(EOL);
+    writer.finish()
+}
+
+/// Decode `rows` of `bitmap`, set bits are black
+fn decode_rows(data: &[u8], bitmap: &mut Bitmap, rows: Range<usize>) -> Result<(), Error> {
+    let width = bitmap.width();
+    let tables = [decode_table(false), decode_table(true)];
+    let mut reader = BitReader { data, pos: 0 };
+    let mut reference_line = Vec::new();
+    let mut line = Vec::new();
+
+    for y in rows {
+        line.clear();
+        let (mut a0, mut black) = (-1, false);
+        let mut start = 0;
+
+        while a0 < width as isize {
+            let (b1, b2) = reference(&reference_line, &mut start, a0, black, width);
+            if reader.matches(PASS)? {
+                a0 = b2 as isize;
+            } else if reader.matches(HORIZONTAL)? {
+                let a1 = a0.max(0) as usize + reader.run(&tables[black as usize])?;
+                let a2 = a1 + reader.run(&tables[!black as usize])?;
+                if a2 > width {
+                    return Err(invalid("run past end of line"));
+                }
+                line.push(a1);
+                line.push(a2);
+                a0 = a2 as isize;
+            } else if let Some(d) = VERTICAL
+                .iter()
+                .position(|c| reader.peek(c.0 as u32) == c.1 as u32)
+            {
+                reader.consume(VERTICAL[d].0 as u32)?;
+                let a1 = b1 as isize + d as isize - 3;
+                if a1 < a0.max(0) || a1 > width as isize {
+                    return Err(invalid("change out of bounds"));
+                }
+                line.push(a1 as usize);
+                a0 = a1;
+                black = !black;
+            } else {
+                return Err(invalid("unsupported mode"));
+            }
+        }
+
+        while line.last().is_some_and(|x| *x >= width) {
+            line.pop();
+        }
+
+        let row = bitmap.row_mut(y);
+        row.iter_mut().for_each(|x| *x = 0);
+        for (i, x) in line.iter().enumerate().step_by(2) {
+            set_range(row, *x, line.get(i + 1).copied().unwrap_or(width));
+        }
+
+        std::mem::swap(&mut reference_line, &mut line);
+    }
+
+    Ok(())
+}
+
+/// Decode a `width` by `height` bitmap, set bits are black
+pub fn decode(data: &[u8], width: usize, height: usize) -> Result<Bitmap, Error> {
+    check_size(data, width, height)?;
+    let mut bitmap = Bitmap::new(width, height);
+    decode_rows(data, &mut bitmap, 0..height)?;
+    Ok(bitmap)
+}
+
+/// Encode a bitmap as a single strip, Group 4 compressed TIFF file
+pub fn encode_tiff(bitmap: &Bitmap) -> Vec<u8> {
+    let mut strip = encode(&!bitmap);
+    let strip_len = strip.len();
+
+    // The IFD has to start on a word boundary
+    if strip.len() % 2 == 1 {
+        strip.push(0);
+    }
+
+    let mut data = b"II*\0".to_vec();
+    data.extend((8 + strip.len() as u32).to_le_bytes());
+    data.extend(strip);
+
+    let (short, long) = (3, 4);
+    let entries: [(u16, u16, u32); 9] = [
+        (256, long, bitmap.width() as u32),
+        (257, long, bitmap.height() as u32),
+        (258, short, 1),
+        (259, short, 4),
+        (262, short, 0),
+        (273, long, 8),
+        (277, short, 1),
+        (278, long, bitmap.height() as u32),
+        (279, long, strip_len as u32),
+    ];
+    data.extend((entries.len() as u16).to_le_bytes());
+    for (tag, kind, value) in &entries {
+        data.extend(tag.to_le_bytes());
+        data.extend(kind.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(value.to_le_bytes());
+    }
+    data.extend(0u32.to_le_bytes());
+    data
+}
+
+/// Decode the first image of a bilevel TIFF file, which can be either uncompressed or Group 4
+/// compressed
+pub fn decode_tiff(data: &[u8]) -> Result<Bitmap, Error> {
+    let (tiff, offset) = Tiff::new(data).map_err(tiff_error)?;
+    let (entries, _) = tiff.ifd(offset).map_err(tiff_error)?;
+    let find = |tag| entries.iter().find(|e| e.tag == tag);
+    let get = |tag, default: Option<u32>| match find(tag) {
+        Some(e) => tiff.uint(e, 0).map(|x| x as usize).map_err(tiff_error),
+        None => default
+            .map(|x| x as usize)
+            .ok_or_else(|| Error::CannotReadImage(format!("missing TIFF tag {}", tag))),
+    };
+
+    let width = get(256, None)?;
+    let height = get(257, None)?;
+    if get(258, Some(1))? != 1 || get(277, Some(1))? != 1 {
+        return Err(Error::CannotReadImage("TIFF file is not bilevel".into()));
+    }
+    let compression = get(259, Some(1))?;
+    if compression != 1 && compression != 4 {
+        return Err(Error::CannotReadImage(format!(
+            "unsupported TIFF compression: {}",
+            compression
+        )));
+    }
+    let photometric = get(262, Some(0))?;
+    if photometric > 1 {
+        return Err(Error::CannotReadImage(format!(
+            "unsupported TIFF photometric interpretation: {}",
+            photometric
+        )));
+    }
+    let reverse = get(266, Some(1))? == 2;
+    let rows_per_strip = get(278, Some(u32::MAX))?.clamp(1, height.max(1));
+
+    let (offsets, counts) = match (find(273), find(279)) {
+        (Some(o), Some(c)) => (o, c),
+        _ => return Err(Error::CannotReadImage("missing TIFF strips".into())),
+    };
+
+    let row_bytes = width.div_ceil(8);
+    check_size(data, width, height)?;
+    if compression == 1 && row_bytes.saturating_mul(height) > data.len() {
+        return Err(Error::CannotReadImage("TIFF strip too short".into()));
+    }
+
+    let mut bitmap = Bitmap::new(width, height);
+    for (i, y) in (0..height).step_by(rows_per_strip).enumerate() {
+        let offset = tiff.uint(offsets, i).map_err(tiff_error)? as usize;
+        let count = tiff.uint(counts, i).map_err(tiff_error)? as usize;
+        let mut strip = offset
+            .checked_add(count)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| Error::CannotReadImage("TIFF strip out of bounds".into()))?
+            .to_vec();
+        if reverse {
+            strip.iter_mut().for_each(|b| *b = b.reverse_bits());
+        }
+
+        let rows = y..(y + rows_per_strip).min(height);
+        if compression == 4 {
+            decode_rows(&strip, &mut bitmap, rows)?;
+        } else {
+            if strip.len() < rows.len() * row_bytes {
+                return Err(Error::CannotReadImage("TIFF strip too short".into()));
+            }
+            for (y, row) in rows.zip(strip.chunks(row_bytes.max(1))) {
+                bitmap.set_packed_row(y, row);
+            }
+        }
+    }
+
+    // With WhiteIsZero set bits are black
+    if photometric == 0 {
+        bitmap.invert();
+    }
+    Ok(bitmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefix_free(codes: &[Code]) -> bool {
+        codes.iter().enumerate().all(|(i, &(a_len, a))| {
+            codes
+                .iter()
+                .enumerate()
+                .all(|(j, &(b_len, b))| i == j || b_len < a_len || (b >> (b_len - a_len)) != a)
+        })
+    }
+
+    fn pattern(width: usize, height: usize) -> Bitmap {
+        let mut bitmap = Bitmap::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let cx = x as isize - width as isize / 2;
+                let cy = y as isize - height as isize / 2;
+                let circle = cx * cx + cy * cy < (width * width / 9) as isize;
+                let stripes = (x / (y % 7 + 1)) % 3 == 0;
+                bitmap.set(x, y, circle ^ (stripes && y % 5 < 2));
+            }
+        }
+        bitmap
+    }
+
+    #[test]
+    fn test_g4_tables() {
+        for black in [false, true] {
+            let (terminating, makeup) = run_tables(black);
+            let codes: Vec<Code> = terminating
+                .iter()
+                .chain(makeup.iter())
+                .chain(EXTENDED_MAKEUP.iter())
+                .copied()
+                .collect();
+            assert!(prefix_free(&codes));
+        }
+
+        let mut modes = VERTICAL.to_vec();
+        modes.extend([PASS, HORIZONTAL, EOL]);
+        assert!(prefix_free(&modes));
+    }
+
+    #[test]
+    fn test_g4_white() {
+        // Every row of a white image is a single V0 code followed by the EOFB code
+        let data = encode(&Bitmap::new(100, 16));
+        assert_eq!(data, [0xff, 0xff, 0x00, 0x10, 0x01]);
+        assert_eq!(decode(&data, 100, 16).unwrap(), Bitmap::new(100, 16));
+    }
+
+    #[test]
+    fn test_g4_roundtrip() {
+        for &(width, height) in &[(1, 1), (63, 9), (64, 20), (200, 50), (3000, 4)] {
+            let bitmap = pattern(width, height);
+            let data = encode(&bitmap);
+            assert_eq!(decode(&data, width, height).unwrap(), bitmap);
+            assert!(decode(&data, width, height + 1).is_err());
+
+            let mut long_runs = Bitmap::new(width, height);
+            long_runs.fill(true);
+            for y in 0..height {
+                long_runs.set((y * 17) % width, y, false);
+            }
+            let data = encode(&long_runs);
+            assert_eq!(decode(&data, width, height).unwrap(), long_runs);
+        }
+    }
+
+    #[test]
+    fn test_g4_tiff() {
+        let bitmap = pattern(77, 33);
+        let data = encode_tiff(&bitmap);
+        assert_eq!(decode_tiff(&data).unwrap(), bitmap);
+
+        // Uncompressed BlackIsZero data in two strips with reversed fill order
+        let mut data = b"II*\0".to_vec();
+        data.extend(10u32.to_le_bytes());
+        data.extend([0b101_00000u8.reverse_bits(), 0b011_00000u8.reverse_bits()]);
+        let entries: [(u16, u16, u32, u32); 8] = [
+            (256, 3, 1, 3),
+            (257, 3, 1, 2),
+            (259, 3, 1, 1),
+            (262, 3, 1, 1),
+            (266, 3, 1, 2),
+            (273, 3, 2, 8 | 9 << 16),
+            (278, 3, 1, 1),
+            (279, 3, 2, 1 | 1 << 16),
+        ];
+        data.extend((entries.len() as u16).to_le_bytes());
+        for (tag, kind, count, value) in &entries {
+            data.extend(tag.to_le_bytes());
+            data.extend(kind.to_le_bytes());
+            data.extend(count.to_le_bytes());
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(0u32.to_le_bytes());
+
+        let bitmap = decode_tiff(&data).unwrap();
+        assert_eq!(bitmap.shape(), (3, 2));
+        assert_eq!(
+            (0..2)
+                .flat_map(|y| (0..3).map(move |x| (x, y)))
+                .map(|(x, y)| bitmap.get(x, y))
+                .collect::<Vec<_>>(),
+            [true, false, true, false, true, true]
+        );
+
+        // Sizes that can't fit in the file are rejected before allocating
+        for entry in [12, 24] {
+            data[entry + 2..entry + 4].copy_from_slice(&4u16.to_le_bytes());
+            data[entry + 8..entry + 12].copy_from_slice(&4_000_000_000u32.to_le_bytes());
+        }
+        assert!(decode_tiff(&data).is_err());
+        assert!(decode(&[0; 20], 4_000_000_000, 4_000_000_000).is_err());
+        assert!(decode(&[0; 20], 100, 1000).is_err());
+    }
+}
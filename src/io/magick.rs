@@ -2,8 +2,10 @@ use std::io::{Read, Write};
 use std::num::ParseIntError;
 use std::path::Path;
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::usize;
 
+use crate::io::{exif::Rational, BaseType};
 use crate::{Color, Image, Type};
 
 #[derive(Debug, thiserror::Error)]
@@ -32,8 +34,29 @@ pub struct Magick {
     convert: &'static [&'static str],
 }
 
-pub fn kind<C: Color>() -> String {
-    format!("{}:-", C::NAME)
+/// Get the raw format name used to pass pixels to ImageMagick/GraphicsMagick, `Channels` is only
+/// supported when it has the same layout as `Gray`, `Rgb` or `Rgba` and premultiplied colors are
+/// not supported. Colors with a `CHANNEL_ORDER` use the equivalent `Gray`, `GrayAlpha`, `Rgb` or
+/// `Rgba` format. `Rec2020`, `Rec2100Pq` and `Rec2100Hlg` are written as RGB with the stored values,
+/// the file isn't tagged with BT.2020 primaries or the transfer function
+pub fn kind<C: Color>() -> Result<String, Error> {
+    let name = match (C::NAME, C::CHANNELS) {
+        (_, 2) if !C::CHANNEL_ORDER.is_empty() => "graya",
+        (_, 3) if !C::CHANNEL_ORDER.is_empty() => "rgb",
+        (_, 4) if !C::CHANNEL_ORDER.is_empty() => "rgba",
+        ("channels", 1) => "gray",
+        ("channels", 3) => "rgb",
+        ("channels", 4) => "rgba",
+        ("channels", _) => return Err(Error::InvalidColor),
+        ("srgb", _)
+        | ("linear_rgb", _)
+        | ("rec2020", _)
+        | ("rec2100_pq", _)
+        | ("rec2100_hlg", _) => "rgb",
+        _ if C::PREMULTIPLIED => return Err(Error::InvalidColor),
+        (name, _) => name,
+    };
+    Ok(format!("{}:-", name))
 }
 
 fn depth<T: Type, C: Color>(cmd: &mut Command) {
@@ -42,7 +65,7 @@ fn depth<T: Type, C: Color>(cmd: &mut Command) {
     cmd.arg(format!("{}", depth));
 
     if T::is_float() {
-        cmd.args(&["-define", "quantum:format=floating-point"]);
+        cmd.args(["-define", "quantum:format=floating-point"]);
     }
 }
 
@@ -58,6 +81,50 @@ pub const GM: Magick = Magick {
 
 pub static mut DEFAULT: Magick = IM;
 
+/// Get a unique path in the system temporary directory
+fn temp_path(ext: &str) -> std::path::PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+    std::env::temp_dir().join(format!("image2-{}-{}.{}", std::process::id(), n, ext))
+}
+
+/// Run `cmd`, writing `data` to stdin
+fn pipe(cmd: &mut Command, data: &[u8]) -> Result<(), Error> {
+    let mut proc = match cmd.spawn() {
+        Ok(c) => c,
+        Err(_) => return Err(Error::UnableToExecuteCommand),
+    };
+
+    {
+        let mut stdin = proc.stdin.take().unwrap();
+        match stdin.write_all(data) {
+            Ok(()) => (),
+            Err(_) => return Err(Error::ErrorWritingImage),
+        }
+        let _ = stdin.flush();
+    }
+
+    match proc.wait() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(Error::UnableToExecuteCommand),
+    }
+}
+
+/// Parse page geometry formatted as `WxH+X+Y`
+fn parse_page(s: &str) -> Option<(usize, usize, isize, isize)> {
+    let i = s.find(['+', '-'])?;
+    let (size, offs) = s.split_at(i);
+    let j = offs[1..].find(['+', '-'])? + 1;
+    let (x, y) = offs.split_at(j);
+    let mut size = size.split('x');
+    let w = size.next()?.parse().ok()?;
+    let h = size.next()?.parse().ok()?;
+    if w == 0 || h == 0 {
+        return None;
+    }
+    Some((w, h, x.parse().ok()?, y.parse().ok()?))
+}
+
 /// Change default command
 pub fn set_default(magick: Magick) {
     unsafe {
@@ -70,7 +137,7 @@ impl Magick {
     pub fn get_image_shape<P: AsRef<Path>>(&self, path: P) -> Result<(usize, usize), Error> {
         let identify = Command::new(self.identify[0])
             .args(self.identify[1..].iter())
-            .args(&["-format", "%w %h"])
+            .args(["-format", "%w %h"])
             .arg(path.as_ref())
             .output();
 
@@ -99,14 +166,118 @@ impl Magick {
         }
     }
 
+    /// Get the storage type and number of channels of an image using identify command
+    pub fn get_image_format<P: AsRef<Path>>(&self, path: P) -> Result<(BaseType, usize), Error> {
+        let identify = Command::new(self.identify[0])
+            .args(self.identify[1..].iter())
+            .args(["-format", "%z|%[channels]|%[quantum:format]|%m;"])
+            .arg(path.as_ref())
+            .output();
+
+        let info = match identify {
+            Ok(info) => info,
+            Err(_) => return Err(Error::InvalidColor),
+        };
+
+        let info = match String::from_utf8(info.stdout) {
+            Ok(info) => info,
+            Err(_) => return Err(Error::InvalidColor),
+        };
+
+        // Only the first frame is used, EXR files always store floating point data even when the
+        // quantum format isn't set
+        let mut t = info
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .split('|')
+            .map(str::trim);
+        let (depth, channels, quantum, format) = (t.next(), t.next(), t.next(), t.next());
+        let float = quantum == Some("floating-point") || format == Some("EXR");
+        let base = match (depth, float) {
+            (Some("8"), _) => BaseType::UInt8,
+            (Some("16"), false) => BaseType::UInt16,
+            (Some("16"), true) => BaseType::Half,
+            (Some("32"), false) => BaseType::UInt32,
+            (Some("32"), true) => BaseType::Float,
+            (Some("64"), false) => BaseType::UInt64,
+            (Some("64"), true) => BaseType::Double,
+            _ => return Err(Error::InvalidColor),
+        };
+
+        let channels = match channels {
+            Some("gray") => 1,
+            Some("graya") => 2,
+            Some("srgb") | Some("rgb") => 3,
+            Some("srgba") | Some("rgba") => 4,
+            _ => return Err(Error::InvalidColor),
+        };
+
+        Ok((base, channels))
+    }
+
+    /// Read the page geometry and resolution using identify command
+    pub fn get_image_geometry<P: AsRef<Path>, T: Type, C: Color>(
+        &self,
+        path: P,
+        meta: &mut crate::Meta<T, C>,
+    ) -> Result<(), Error> {
+        let identify = Command::new(self.identify[0])
+            .args(self.identify[1..].iter())
+            .args(["-format", "%g|%x|%y|%U"])
+            .arg(path.as_ref())
+            .output();
+
+        let info = match identify {
+            Ok(info) => info,
+            Err(_) => return Err(Error::InvalidImageShape),
+        };
+
+        let info = match String::from_utf8(info.stdout) {
+            Ok(info) => info,
+            Err(_) => return Err(Error::InvalidImageShape),
+        };
+
+        let t: Vec<&str> = info.trim().split('|').collect();
+        if t.len() < 4 {
+            return Err(Error::InvalidImageShape);
+        }
+
+        if let Some((w, h, x, y)) = parse_page(t[0]) {
+            meta.full_width = w;
+            meta.full_height = h;
+            meta.x = meta.full_x + x;
+            meta.y = meta.full_y + y;
+        }
+
+        let res = |s: &str| {
+            s.split_whitespace()
+                .next()
+                .and_then(|s| s.parse::<f64>().ok())
+        };
+        let scale = match t[3].trim() {
+            "PixelsPerInch" => Some(1.0),
+            "PixelsPerCentimeter" => Some(2.54),
+            _ => None,
+        };
+        if let (Some(x), Some(y), Some(scale)) = (res(t[1]), res(t[2]), scale) {
+            meta.dpi = Some((Rational::from_f64(x * scale), Rational::from_f64(y * scale)));
+        }
+
+        Ok(())
+    }
+
     /// Read image from disk using ImageMagick/GraphicsMagick
+    ///
+    /// The stored values are returned as-is, except for `Srgb` and `LinearRgb` which are converted
+    /// from the transfer function of the file, see `io::file_transfer`
     pub fn read<P: AsRef<Path>, T: Type, C: Color>(&self, path: P) -> Result<Image<T, C>, Error> {
         let (width, height) = match self.get_image_shape(&path) {
             Ok((width, height)) => (width, height),
             Err(e) => return Err(e),
         };
 
-        let kind = kind::<C>();
+        let kind = kind::<C>()?;
         let mut cmd = Command::new(self.convert[0]);
         cmd.args(self.convert[1..].iter()).arg(path.as_ref());
         depth::<T, C>(&mut cmd);
@@ -121,59 +292,93 @@ impl Magick {
             return Err(Error::InvalidImageData);
         }
 
-        Ok(Image {
+        let mut image = Image {
             meta: crate::Meta::new(width, height),
             data: unsafe {
                 let mut data: Vec<T> = std::mem::transmute(cmd.stdout);
                 data.set_len(width * height * C::CHANNELS);
                 data
             },
-        })
+        };
+
+        image.restore_channel_order();
+        crate::io::decode_file_transfer(&mut image, crate::io::path_transfer(path.as_ref()));
+
+        // Missing geometry isn't an error, the defaults match the data window
+        let _ = self.get_image_geometry(&path, &mut image.meta);
+
+        Ok(image)
     }
 
     /// Write image to disk using ImageMagick/GraphicsMagick
+    ///
+    /// The values are written as-is, except for `Srgb` and `LinearRgb` which are converted to the
+    /// transfer function of the file, see `io::file_transfer`
     pub fn write<P: AsRef<Path>, T: Type, C: Color>(
         &self,
         path: P,
         image: &Image<T, C>,
     ) -> Result<(), Error> {
-        let kind = kind::<C>();
+        let image = crate::io::encode_file_transfer(image, crate::io::path_transfer(path.as_ref()));
+        let kind = kind::<C>()?;
         let (width, height, _) = image.shape();
         let size = format!("{}x{}", width, height);
         let mut cmd = Command::new(self.convert[0]);
         cmd.args(self.convert[1..].iter()).stdin(Stdio::piped());
         depth::<T, C>(&mut cmd);
-        cmd.args(&["-size", size.as_str()])
-            .arg(kind)
-            .arg(path.as_ref());
+        cmd.args(["-size", size.as_str()]);
 
-        let mut proc = match cmd.spawn() {
-            Ok(c) => c,
-            Err(_) => return Err(Error::UnableToExecuteCommand),
-        };
+        let meta = &image.meta;
+        if let Some((x, y)) = meta.dpi {
+            cmd.args(["-units", "PixelsPerInch", "-density"])
+                .arg(format!("{}x{}", x.to_f64(), y.to_f64()));
+        }
 
-        {
-            let mut stdin = proc.stdin.take().unwrap();
-            match stdin.write_all(image.buffer()) {
-                Ok(()) => (),
-                Err(_) => return Err(Error::ErrorWritingImage),
-            }
-            let _ = stdin.flush();
+        if meta.has_display_window() {
+            cmd.arg("-page").arg(format!(
+                "{}x{}{:+}{:+}",
+                meta.full_width,
+                meta.full_height,
+                meta.x - meta.full_x,
+                meta.y - meta.full_y
+            ));
         }
 
-        match proc.wait() {
-            Ok(_) => Ok(()),
-            Err(_) => Err(Error::UnableToExecuteCommand),
+        cmd.arg(kind);
+
+        // The profile can only be passed as a file, so it's written to a temporary location
+        let profile = match &image.meta.icc_profile {
+            Some(data) => {
+                let path = temp_path("icc");
+                if std::fs::write(&path, data).is_err() {
+                    return Err(Error::ErrorWritingImage);
+                }
+                cmd.arg("-profile").arg(&path);
+                Some(path)
+            }
+            None => None,
+        };
+
+        cmd.arg(path.as_ref());
+
+        let result = pipe(&mut cmd, image.disk_channel_order().buffer());
+
+        if let Some(profile) = profile {
+            let _ = std::fs::remove_file(profile);
         }
+
+        result
     }
 
-    /// Encode image to an im-memory buffer using ImageMagick/GraphicsMagick
+    /// Encode image to an im-memory buffer using ImageMagick/GraphicsMagick, values are converted
+    /// the same way as `write`
     pub fn encode<T: Type, C: Color>(
         &self,
         format: &str,
         image: &Image<T, C>,
     ) -> Result<Vec<u8>, Error> {
-        let kind = kind::<C>();
+        let image = crate::io::encode_file_transfer(image, crate::io::file_transfer(format));
+        let kind = kind::<C>()?;
         let (width, height, _) = image.shape();
         let size = format!("{}x{}", width, height);
         let mut cmd = Command::new(self.convert[0]);
@@ -181,7 +386,7 @@ impl Magick {
             .stdin(Stdio::piped())
             .stdout(Stdio::piped());
         depth::<T, C>(&mut cmd);
-        cmd.args(&["-size", size.as_str()])
+        cmd.args(["-size", size.as_str()])
             .arg(&kind)
             .arg(format!("{}:-", format));
 
@@ -192,7 +397,7 @@ impl Magick {
 
         {
             let mut stdin = proc.stdin.take().unwrap();
-            match stdin.write_all(image.buffer()) {
+            match stdin.write_all(image.disk_channel_order().buffer()) {
                 Ok(()) => (),
                 Err(_) => return Err(Error::ErrorWritingImage),
             }
@@ -218,6 +423,11 @@ pub fn read<P: AsRef<Path>, T: Type, C: Color>(path: P) -> Result<Image<T, C>, E
     unsafe { DEFAULT.read(path) }
 }
 
+/// Get the storage type and number of channels of an image using default command-line tool
+pub fn get_image_format<P: AsRef<Path>>(path: P) -> Result<(BaseType, usize), Error> {
+    unsafe { (*std::ptr::addr_of!(DEFAULT)).get_image_format(path) }
+}
+
 /// Write image to disk using default command-line tool
 pub fn write<P: AsRef<Path>, T: Type, C: Color>(path: P, image: &Image<T, C>) -> Result<(), Error> {
     unsafe { DEFAULT.write(path, image) }
@@ -1,6 +1,93 @@
 #[cfg(not(feature = "oiio"))]
 pub mod magick;
 
+pub(crate) mod container;
+pub mod exif;
+pub mod g4;
+pub mod pbm;
+
+/// Options used to configure how an image is opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OpenOptions {
+    auto_orient: bool,
+}
+
+impl OpenOptions {
+    /// Create new `OpenOptions` with default values
+    pub fn new() -> OpenOptions {
+        OpenOptions::default()
+    }
+
+    /// Apply EXIF orientation using lossless flips and rotations when loading
+    pub fn auto_orient(mut self, auto_orient: bool) -> Self {
+        self.auto_orient = auto_orient;
+        self
+    }
+
+    /// Returns true when EXIF orientation will be applied
+    pub fn is_auto_orient(&self) -> bool {
+        self.auto_orient
+    }
+
+    /// Open an image using the configured options
+    pub fn open<T: crate::Type, C: crate::Color>(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<crate::Image<T, C>, crate::Error> {
+        crate::Image::open_with(path, self)
+    }
+}
+
+/// Transfer function of the values stored in files with the given extension or format name, EXR,
+/// HDR and PFM files store linear light and all other formats are treated as sRGB encoded
+pub fn file_transfer(format: &str) -> crate::TransferFunction {
+    match format.to_ascii_lowercase().as_str() {
+        "exr" | "hdr" | "pfm" => crate::TransferFunction::Linear,
+        _ => crate::TransferFunction::Srgb,
+    }
+}
+
+/// Transfer function of the values stored in the file at `path`, see `file_transfer`
+pub(crate) fn path_transfer(path: &std::path::Path) -> crate::TransferFunction {
+    file_transfer(
+        path.extension()
+            .and_then(|x| x.to_str())
+            .unwrap_or_default(),
+    )
+}
+
+/// Returns true when `C` is explicit about its transfer function, only `Srgb` and `LinearRgb` are
+/// converted from and to the transfer function of the file, every other color keeps the stored
+/// values
+pub(crate) fn converts_file_transfer<C: crate::Color>() -> bool {
+    matches!(C::NAME, "srgb" | "linear_rgb")
+}
+
+/// Convert values read from a file encoded with `transfer` to the transfer function of `C`
+pub(crate) fn decode_file_transfer<T: crate::Type, C: crate::Color>(
+    image: &mut crate::Image<T, C>,
+    transfer: crate::TransferFunction,
+) {
+    if converts_file_transfer::<C>() && transfer != C::TRANSFER {
+        image.map_color_channels(|x| C::TRANSFER.encode(transfer.decode(x)));
+    }
+}
+
+/// Convert values to `transfer` before writing them to a file, the inverse of
+/// `decode_file_transfer`
+pub(crate) fn encode_file_transfer<T: crate::Type, C: crate::Color>(
+    image: &crate::Image<T, C>,
+    transfer: crate::TransferFunction,
+) -> std::borrow::Cow<'_, crate::Image<T, C>> {
+    if !converts_file_transfer::<C>() || transfer == C::TRANSFER {
+        return std::borrow::Cow::Borrowed(image);
+    }
+
+    let mut image = image.clone();
+    image.map_color_channels(|x| transfer.encode(C::TRANSFER.decode(x)));
+    std::borrow::Cow::Owned(image)
+}
+
 /// `BaseType` is compatible with OpenImageIO's `TypeDesc::BASETYPE`
 ///
 /// This enum is used to convert from `Type` into a representation that can be used with OIIO
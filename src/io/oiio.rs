@@ -83,12 +83,15 @@ impl Output {
     /// Write an image to the file
     ///
     /// Note: `image` dimensions and type will take precendence over the ImageSpec
-    pub fn write<T: Type, C: Color>(self, image: &Image<T, C>) -> Result<(), Error> {
+    pub fn write<T: Type, C: Color>(mut self, image: &Image<T, C>) -> Result<(), Error> {
+        let image = super::encode_file_transfer(image, super::path_transfer(&self.path));
+        self.spec.set_meta(&image.meta);
         let base_type = T::BASE;
         let path: &std::path::Path = self.path.as_ref();
         let path_str = std::ffi::CString::new(path.to_string_lossy().as_bytes().to_vec()).unwrap();
         let filename = path_str.as_ptr();
-        let pixels = image.data.as_ptr();
+        let data = image.disk_channel_order();
+        let pixels = data.data.as_ptr();
         let (width, height, channels) = image.shape();
         let out = self.image_output;
         let spec = &self.spec;
@@ -110,11 +113,16 @@ impl Output {
     ///
     /// Note: `image` dimensions and type will take precendence over the ImageSpec
     pub fn append<T: Type, C: Color>(&mut self, image: &Image<T, C>) -> Result<(), Error> {
+        let image = super::encode_file_transfer(image, super::path_transfer(&self.path));
+        if self.index == 0 {
+            self.spec.set_meta(&image.meta);
+        }
         let base_type = T::BASE;
         let path: &std::path::Path = self.path.as_ref();
         let path_str = std::ffi::CString::new(path.to_string_lossy().as_bytes().to_vec()).unwrap();
         let filename = path_str.as_ptr();
-        let pixels = image.data.as_ptr();
+        let data = image.disk_channel_order();
+        let pixels = data.data.as_ptr();
         let (width, height, channels) = image.shape();
         let out = self.image_output;
         let spec = &self.spec;
@@ -215,9 +223,12 @@ impl Input {
         let path_str = std::ffi::CString::new(path.to_string_lossy().as_bytes().to_vec()).unwrap();
         let filename = path_str.as_ptr();
 
+        // Unassociated alpha is kept as-is, `read` converts to the requested color
         let input = unsafe {
             cpp!([filename as "const char *", tmp as "ImageSpec*"] ->  *mut u8 as "std::unique_ptr<ImageInput>" {
-                auto input = ImageInput::open(filename);
+                ImageSpec config;
+                config.attribute("oiio:UnassociatedAlpha", 1);
+                auto input = ImageInput::open(filename, &config);
                 if (!input) {
                     return nullptr;
                 }
@@ -279,33 +290,72 @@ impl Input {
         Ok(())
     }
 
+    /// Read using the given color, then convert. The stored values are decoded to linear light
+    /// first when `C` is converted from the transfer function of the file
+    fn read_as<D: Color, T: Type, C: Color>(&self) -> Result<Image<T, C>, Error> {
+        let mut image = Image::<f32, D>::new(self.spec.width(), self.spec.height());
+        self.read_into(&mut image)?;
+        if super::converts_file_transfer::<C>() {
+            let transfer = super::path_transfer(&self.path);
+            image.map_color_channels(|x| transfer.decode(x));
+        }
+        Ok(image.convert())
+    }
+
     /// Read to new image
     ///
-    /// Note: the `convert` method may be called if the requested color doesn't match
+    /// Note: the `convert` method may be called if the requested color doesn't match. The stored
+    /// values are returned as-is, except for `Srgb` and `LinearRgb` which are converted from the
+    /// transfer function of the file, see `io::file_transfer`
     pub fn read<T: Type, C: Color>(&self) -> Result<Image<T, C>, Error> {
         let nchannels = self.spec.nchannels();
 
-        // `convert` is called if the channels don't match the image on disk or the color is not
-        // Gray, Rgb, or Rgba
-        if C::CHANNELS != nchannels || !["gray", "rgb", "rgba"].contains(&C::NAME) {
-            if nchannels == 1 {
-                let mut image = Image::<f32, Gray>::new(self.spec.width(), self.spec.height());
-                self.read_into(&mut image)?;
-                Ok(image.convert())
-            } else if nchannels == 4 {
-                let mut image = Image::<f32, Rgba>::new(self.spec.width(), self.spec.height());
-                self.read_into(&mut image)?;
-                Ok(image.convert())
-            } else {
-                let mut image = Image::<f32, Rgb>::new(self.spec.width(), self.spec.height());
-                self.read_into(&mut image)?;
-                Ok(image.convert())
+        // Unless the file stores unassociated alpha OIIO returns premultiplied values
+        let premultiplied = !self.spec.is_unassociated_alpha();
+
+        // `convert` is called if the channels don't match the image on disk or the color doesn't
+        // map directly to the channels on disk
+        let direct = [
+            "gray",
+            "graya",
+            "premultiplied_graya",
+            "rgb",
+            "srgb",
+            "linear_rgb",
+            "rec2020",
+            "rec2100_pq",
+            "rec2100_hlg",
+            "rgba",
+            "premultiplied_rgba",
+            "bgr",
+            "bgra",
+            "argb",
+            "channels",
+        ];
+        let mut image: Image<T, C> = if C::CHANNELS != nchannels || !direct.contains(&C::NAME) {
+            match (nchannels, premultiplied) {
+                (1, _) => self.read_as::<Gray, _, _>()?,
+                (2, true) => self.read_as::<PremultipliedGrayAlpha, _, _>()?,
+                (2, false) => self.read_as::<GrayAlpha, _, _>()?,
+                (4, true) => self.read_as::<PremultipliedRgba, _, _>()?,
+                (4, false) => self.read_as::<Rgba, _, _>()?,
+                _ => self.read_as::<Rgb, _, _>()?,
             }
         } else {
             let mut image = Image::new(self.spec.width(), self.spec.height());
             self.read_into(&mut image)?;
-            Ok(image)
-        }
+            image.restore_channel_order();
+            if premultiplied && !C::PREMULTIPLIED {
+                image.apply_alpha(color::unpremultiply);
+            } else if !premultiplied && C::PREMULTIPLIED {
+                image.apply_alpha(|x, alpha| x * alpha);
+            }
+            super::decode_file_transfer(&mut image, super::path_transfer(&self.path));
+            image
+        };
+
+        self.spec.get_meta(&mut image.meta);
+        Ok(image)
     }
 }
 
@@ -383,6 +433,147 @@ impl ImageSpec {
         }
     }
 
+    /// Get horizontal origin of the data window
+    pub fn x(&self) -> isize {
+        unsafe {
+            cpp!([self as "const ImageSpec*"] -> isize as "ptrdiff_t" {
+                return (ptrdiff_t)self->x;
+            })
+        }
+    }
+
+    /// Get vertical origin of the data window
+    pub fn y(&self) -> isize {
+        unsafe {
+            cpp!([self as "const ImageSpec*"] -> isize as "ptrdiff_t" {
+                return (ptrdiff_t)self->y;
+            })
+        }
+    }
+
+    /// Get horizontal origin of the display window
+    pub fn full_x(&self) -> isize {
+        unsafe {
+            cpp!([self as "const ImageSpec*"] -> isize as "ptrdiff_t" {
+                return (ptrdiff_t)self->full_x;
+            })
+        }
+    }
+
+    /// Get vertical origin of the display window
+    pub fn full_y(&self) -> isize {
+        unsafe {
+            cpp!([self as "const ImageSpec*"] -> isize as "ptrdiff_t" {
+                return (ptrdiff_t)self->full_y;
+            })
+        }
+    }
+
+    /// Get width of the display window
+    pub fn full_width(&self) -> usize {
+        unsafe {
+            cpp!([self as "const ImageSpec*"] -> usize as "size_t" {
+                return (size_t)self->full_width;
+            })
+        }
+    }
+
+    /// Get height of the display window
+    pub fn full_height(&self) -> usize {
+        unsafe {
+            cpp!([self as "const ImageSpec*"] -> usize as "size_t" {
+                return (size_t)self->full_height;
+            })
+        }
+    }
+
+    /// Set origin of the data window
+    pub fn set_origin(&mut self, x: isize, y: isize) {
+        unsafe {
+            cpp!([self as "ImageSpec*", x as "ptrdiff_t", y as "ptrdiff_t"] {
+                self->x = (int)x;
+                self->y = (int)y;
+            });
+        }
+    }
+
+    /// Set the display window
+    pub fn set_full(&mut self, x: isize, y: isize, width: usize, height: usize) {
+        unsafe {
+            cpp!([self as "ImageSpec*", x as "ptrdiff_t", y as "ptrdiff_t", width as "size_t", height as "size_t"] {
+                self->full_x = (int)x;
+                self->full_y = (int)y;
+                self->full_width = (int)width;
+                self->full_height = (int)height;
+            });
+        }
+    }
+
+    /// Get pixel aspect ratio
+    pub fn pixel_aspect(&self) -> f64 {
+        match self.get_attr("PixelAspectRatio") {
+            Some(Attr::Float(f)) if f > 0.0 => f as f64,
+            _ => 1.0,
+        }
+    }
+
+    /// Get resolution in pixels per inch
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        let x = match self.get_attr("XResolution") {
+            Some(Attr::Float(f)) => f as f64,
+            _ => return None,
+        };
+
+        let y = match self.get_attr("YResolution") {
+            Some(Attr::Float(f)) => f as f64,
+            _ => x,
+        };
+
+        match self.get_attr("ResolutionUnit") {
+            Some(Attr::String("cm")) => Some((x * 2.54, y * 2.54)),
+            Some(Attr::String("in")) | None => Some((x, y)),
+            _ => None,
+        }
+    }
+
+    /// Returns true when the image was opened with unassociated (straight) alpha
+    pub fn is_unassociated_alpha(&self) -> bool {
+        matches!(self.get_attr("oiio:UnassociatedAlpha"), Some(Attr::Int(1)))
+    }
+
+    /// Copy window, pixel aspect ratio and resolution from image metadata, alpha is marked as
+    /// unassociated when the color has straight alpha
+    pub fn set_meta<T: Type, C: Color>(&mut self, meta: &Meta<T, C>) {
+        if C::ALPHA && !C::PREMULTIPLIED {
+            self.set_attr("oiio:UnassociatedAlpha", 1);
+        }
+        self.set_origin(meta.x, meta.y);
+        self.set_full(meta.full_x, meta.full_y, meta.full_width, meta.full_height);
+        self.set_attr("PixelAspectRatio", meta.pixel_aspect.to_f64() as f32);
+        if let Some((x, y)) = meta.dpi {
+            self.set_attr("XResolution", x.to_f64() as f32);
+            self.set_attr("YResolution", y.to_f64() as f32);
+            self.set_attr("ResolutionUnit", "in");
+        }
+    }
+
+    /// Copy window, pixel aspect ratio and resolution into image metadata
+    pub fn get_meta<T: Type, C: Color>(&self, meta: &mut Meta<T, C>) {
+        meta.x = self.x();
+        meta.y = self.y();
+        meta.full_x = self.full_x();
+        meta.full_y = self.full_y();
+        meta.full_width = self.full_width();
+        meta.full_height = self.full_height();
+        meta.pixel_aspect = io::exif::Rational::from_f64(self.pixel_aspect());
+        meta.dpi = self.dpi().map(|(x, y)| {
+            (
+                io::exif::Rational::from_f64(x),
+                io::exif::Rational::from_f64(y),
+            )
+        });
+    }
+
     /// Get image format
     pub fn format(&self) -> BaseType {
         unsafe {
@@ -437,6 +628,17 @@ impl ImageSpec {
         }
     }
 
+    /// Set the ICC profile to embed when writing an image
+    pub fn set_icc_profile(&mut self, profile: &[u8]) {
+        let len = profile.len();
+        let data = profile.as_ptr();
+        unsafe {
+            cpp!([self as "ImageSpec*", len as "size_t", data as "const unsigned char*"] {
+                self->attribute("ICCProfile", TypeDesc(TypeDesc::UINT8, (int)len), data);
+            });
+        }
+    }
+
     pub fn colorspace(&self) -> Option<&str> {
         match self.get_attr("oiio:ColorSpace") {
             Some(Attr::String(s)) => Some(s),
@@ -0,0 +1,110 @@
+//! Netpbm bitmap (PBM) encoding and decoding
+//!
+//! Both the plain (`P1`) and binary (`P4`) variants are supported when reading, `P4` is always
+//! used when writing. In PBM files 1 is black, so bits are inverted relative to `Bitmap`.
+
+use crate::*;
+
+fn invalid(s: &str) -> Error {
+    Error::CannotReadImage(format!("invalid PBM file: {}", s))
+}
+
+struct Header<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Header<'a> {
+    /// Skip whitespace and comments
+    fn skip(&mut self) {
+        while let Some(c) = self.data.get(self.pos) {
+            match c {
+                b'#' => {
+                    while !matches!(self.data.get(self.pos), None | Some(b'\n') | Some(b'\r')) {
+                        self.pos += 1;
+                    }
+                }
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, Error> {
+        self.skip();
+        let start = self.pos;
+        while self.data.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| invalid("expected number"))
+    }
+}
+
+/// Decode a PBM file
+pub fn decode(data: &[u8]) -> Result<Bitmap, Error> {
+    let binary = match data.get(0..2) {
+        Some(b"P1") => false,
+        Some(b"P4") => true,
+        _ => return Err(invalid("missing header")),
+    };
+
+    let mut header = Header { data, pos: 2 };
+    let width = header.number()?;
+    let height = header.number()?;
+
+    // The header is checked against the amount of data before allocating, plain files use at
+    // least one byte per pixel
+    let row_bytes = if binary { width.div_ceil(8) } else { width };
+    let len = row_bytes
+        .checked_mul(height)
+        .ok_or_else(|| invalid("image too large"))?;
+    if len > data.len().saturating_sub(header.pos) {
+        return Err(invalid("unexpected end of data"));
+    }
+
+    let mut bitmap = Bitmap::new(width, height);
+    if binary {
+        // Exactly one whitespace character separates the header from the data
+        let start = header.pos + 1;
+        let pixels = data
+            .get(start..start + len)
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+        for (y, row) in pixels
+            .chunks_exact(row_bytes.max(1))
+            .take(height)
+            .enumerate()
+        {
+            bitmap.set_packed_row(y, row);
+        }
+    } else {
+        for y in 0..height {
+            for x in 0..width {
+                header.skip();
+                let black = match data.get(header.pos) {
+                    Some(b'0') => false,
+                    Some(b'1') => true,
+                    Some(_) => return Err(invalid("expected 0 or 1")),
+                    None => return Err(invalid("unexpected end of data")),
+                };
+                header.pos += 1;
+                bitmap.set(x, y, black);
+            }
+        }
+    }
+
+    bitmap.invert();
+    Ok(bitmap)
+}
+
+/// Encode a bitmap as a binary PBM file
+pub fn encode(bitmap: &Bitmap) -> Vec<u8> {
+    let inverted = !bitmap;
+    let mut data = format!("P4\n{} {}\n", bitmap.width(), bitmap.height()).into_bytes();
+    for y in 0..bitmap.height() {
+        data.extend(inverted.packed_row(y));
+    }
+    data
+}
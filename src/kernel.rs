@@ -61,24 +61,90 @@ kernel_from!(
 );
 
 impl Filter for Kernel {
-    fn compute_at(
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        let r2 = (self.rows / 2) as isize;
+        let c2 = (self.cols / 2) as isize;
+
+        // Color channels with straight alpha are weighted by alpha when the kernel computes a
+        // weighted average, other kernels (sharpening, edge detection, scaling) are applied to the
+        // values directly. Channels with a non-linear transfer function are combined in linear
+        // light
+        let meta = input[0].meta();
+        let alpha = meta.alpha_channel().unwrap_or_default();
+        let weighted =
+            meta.has_alpha() && !meta.is_premultiplied() && c != alpha && self.is_averaging();
+
+        let mut f = 0.0;
+        let mut fa = 0.0;
+        let mut a = 0.0;
+        for ky in -r2..=r2 {
+            let kr = &self.data[(ky + r2) as usize];
+            for kx in -c2..=c2 {
+                let (px, py) = ((x as isize + kx) as usize, (y as isize + ky) as usize);
+                let k = kr[(kx + c2) as usize];
+                let x = meta.decode_transfer(c, input[0].get_f(px, py, c));
+                f += x * k;
+                if weighted {
+                    let w = input[0].get_f(px, py, alpha);
+                    fa += x * w * k;
+                    a += w * k;
+                }
+            }
+        }
+
+        let f = if weighted && a.abs() > f64::EPSILON {
+            fa / a
+        } else {
+            f
+        };
+        meta.encode_transfer(c, f)
+    }
+
+    fn compute_pixel<C: Color>(
         &self,
         x: usize,
         y: usize,
-        c: usize,
-        input: &[&Image<impl Type, impl Color>],
-    ) -> f64 {
+        input: &[&impl GenericImage],
+        out: &mut Pixel<C>,
+    ) {
         let r2 = (self.rows / 2) as isize;
         let c2 = (self.cols / 2) as isize;
-        let mut f = 0.0;
+
+        // The neighborhood is walked once for all channels, see `compute_at` for how alpha and
+        // transfer functions are handled
+        let meta = input[0].meta();
+        let alpha = meta.alpha_channel();
+        let weighted = meta.has_alpha() && !meta.is_premultiplied() && self.is_averaging();
+
+        let mut f = Pixel::<C>::new();
+        let mut fa = Pixel::<C>::new();
+        let mut a = 0.0;
         for ky in -r2..=r2 {
             let kr = &self.data[(ky + r2) as usize];
             for kx in -c2..=c2 {
-                let x = input[0].get_f((x as isize + kx) as usize, (y as isize + ky) as usize, c);
-                f += x * kr[(kx + c2) as usize];
+                let (px, py) = ((x as isize + kx) as usize, (y as isize + ky) as usize);
+                let k = kr[(kx + c2) as usize];
+                let w = match alpha {
+                    Some(alpha) if weighted => input[0].get_f(px, py, alpha),
+                    _ => 0.0,
+                };
+                a += w * k;
+                for c in 0..f.len() {
+                    let x = meta.decode_transfer(c, input[0].get_f(px, py, c));
+                    f[c] += x * k;
+                    fa[c] += x * w * k;
+                }
             }
         }
-        f
+
+        for (c, out) in out.iter_mut().enumerate() {
+            let f = if weighted && alpha != Some(c) && a.abs() > f64::EPSILON {
+                fa[c] / a
+            } else {
+                f[c]
+            };
+            *out = meta.encode_transfer(c, f);
+        }
     }
 }
 
@@ -94,6 +160,19 @@ impl Kernel {
         Self::new(x, x)
     }
 
+    /// Returns true when every weight is non-negative and the weights sum to 1, alpha weighting is
+    /// only meaningful for these kernels
+    fn is_averaging(&self) -> bool {
+        let mut sum = 0.0;
+        for k in self.data.iter().flatten() {
+            if *k < 0.0 {
+                return false;
+            }
+            sum += k;
+        }
+        (sum - 1.0).abs() < 1e-6
+    }
+
     /// Ensures the sum of the kernel is <= 1
     pub fn normalize(&mut self) {
         let sum: f64 = self.data.iter().map(|x| -> f64 { x.iter().sum() }).sum();
@@ -186,7 +265,7 @@ macro_rules! op {
                 x: usize,
                 y: usize,
                 c: usize,
-                input: &[&Image<impl Type, impl Color>],
+                input: &[&impl GenericImage],
             ) -> f64 {
                 let r2 = (self.a.rows / 2) as isize;
                 let c2 = (self.a.cols / 2) as isize;
@@ -26,12 +26,17 @@
 
 pub use half::f16;
 
+mod bitmap;
 mod color;
+mod dynamic;
 mod error;
 mod histogram;
 mod image;
 mod pixel;
 mod r#type;
+mod stats;
+mod transfer;
+mod view;
 
 #[cfg(feature = "halide")]
 mod halide_wrapper;
@@ -39,21 +44,36 @@ mod halide_wrapper;
 #[cfg(feature = "halide")]
 pub use halide_runtime as halide;
 
+pub mod colorspace;
 pub mod filter;
+pub mod icc;
 pub mod io;
 pub mod kernel;
+pub mod reduce;
 
 #[cfg(feature = "transforms")]
 pub mod transform;
 
-pub use color::{Color, Convert, Gray, Rgb, Rgba, Xyz};
+pub use bitmap::Bitmap;
+pub use color::{
+    adapt_white_point, Argb, Bgr, Bgra, Bt2020, Bt601, Bt709, Channels, Cmyk, Color, Convert,
+    FullRange, Gray, GrayAlpha, Hsl, Hsv, Lab, Lch, LimitedRange, LinearRgb, Luv, Oklab, Oklch,
+    PremultipliedGrayAlpha, PremultipliedRgba, Premultiply, Rec2020, Rec2100Hlg, Rec2100Pq, Rgb,
+    Rgba, Srgb, Unpremultiply, WhitePoint, Xyz, YCbCr, YCbCrRange, YCbCrStandard, Yuv, D50, D65,
+};
+pub use dynamic::DynamicImage;
 pub use error::Error;
 pub use filter::Filter;
 pub use histogram::Histogram;
 pub use image::{Hash, Image, Meta, Region};
+pub use io::OpenOptions;
 pub use kernel::Kernel;
 pub use pixel::Pixel;
-pub use r#type::Type;
+pub use reduce::Reduce;
+pub use r#type::{Rounding, Type};
+pub use stats::{Stats, StatsAccumulator};
+pub use transfer::TransferFunction;
+pub use view::{GenericImage, GenericImageMut, ImageView, ImageViewMut};
 
 #[cfg(test)]
 mod tests;
@@ -1,12 +1,52 @@
 use crate::*;
 
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
-pub struct Pixel<C: Color>(Box<[f64]>, std::marker::PhantomData<C>);
+/// Number of channels stored inline, this covers every built-in color except `Channels<N>` with
+/// more than 4 channels which falls back to the heap
+const INLINE_CHANNELS: usize = 4;
+
+#[derive(Clone)]
+enum Data {
+    Inline([f64; INLINE_CHANNELS]),
+    Heap(Box<[f64]>),
+}
+
+/// Normalized pixel values, stored on the stack for colors with up to 4 channels
+#[derive(Clone)]
+pub struct Pixel<C: Color>(Data, std::marker::PhantomData<C>);
+
+impl<C: Color> std::fmt::Debug for Pixel<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("Pixel").field(&self.as_ref()).finish()
+    }
+}
+
+impl<C: Color> PartialEq for Pixel<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl<C: Color> PartialOrd for Pixel<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.as_ref().partial_cmp(other.as_ref())
+    }
+}
 
 impl<C: Color> AsRef<[f64]> for Pixel<C> {
     fn as_ref(&self) -> &[f64] {
-        self.0.as_ref()
+        match &self.0 {
+            Data::Inline(data) => &data[..C::CHANNELS],
+            Data::Heap(data) => data,
+        }
+    }
+}
+
+impl<C: Color> AsMut<[f64]> for Pixel<C> {
+    fn as_mut(&mut self) -> &mut [f64] {
+        match &mut self.0 {
+            Data::Inline(data) => &mut data[..C::CHANNELS],
+            Data::Heap(data) => data,
+        }
     }
 }
 
@@ -16,20 +56,43 @@ impl<C: Color> Default for Pixel<C> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<C: Color> serde::Serialize for Pixel<C> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, C: Color> serde::Deserialize<'de> for Pixel<C> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = Vec::<f64>::deserialize(deserializer)?;
+        if data.len() != C::CHANNELS {
+            return Err(serde::de::Error::invalid_length(
+                data.len(),
+                &"one value per channel",
+            ));
+        }
+
+        Ok(Pixel::from_slice(&data))
+    }
+}
 impl<C: Color> Pixel<C> {
     pub fn into_vec(self) -> Vec<f64> {
-        self.0.to_vec()
+        self.as_ref().to_vec()
     }
 
     pub fn new() -> Pixel<C> {
-        Pixel(
-            vec![0.0; C::CHANNELS].into_boxed_slice(),
-            std::marker::PhantomData,
-        )
+        let data = if C::CHANNELS <= INLINE_CHANNELS {
+            Data::Inline([0.0; INLINE_CHANNELS])
+        } else {
+            Data::Heap(vec![0.0; C::CHANNELS].into_boxed_slice())
+        };
+        Pixel(data, std::marker::PhantomData)
     }
 
     pub fn fill<T: Type>(mut self, x: T) -> Self {
-        self.0.iter_mut().for_each(|a| *a = x.to_norm());
+        self.iter_mut().for_each(|a| *a = x.to_norm());
         self
     }
 
@@ -42,18 +105,12 @@ impl<C: Color> Pixel<C> {
     }
 
     pub fn is_alpha(&self, index: usize) -> bool {
-        if C::ALPHA {
-            let len = self.len();
-            return index == len - 1;
-        }
-
-        false
+        C::ALPHA && index == C::ALPHA_CHANNEL
     }
 
     pub fn with_alpha(mut self, value: f64) -> Self {
         if C::ALPHA {
-            let index = self.len() - 1;
-            self[index] = value
+            self[C::ALPHA_CHANNEL] = value
         }
         self
     }
@@ -84,15 +141,27 @@ impl<C: Color> Pixel<C> {
         px
     }
 
+    /// Composite over black and make the pixel opaque
     pub fn blend_alpha(mut self) -> Self {
-        let index = self.len() - 1;
+        if !C::ALPHA {
+            return self;
+        }
+
+        let index = C::ALPHA_CHANNEL;
         let alpha = self[index];
 
-        self.map_in_place(|x| x * alpha);
+        if !C::PREMULTIPLIED {
+            self.map_in_place(|x| x * alpha);
+        }
         self[index] = 1.0;
         self
     }
 
+    /// Get alpha value, 1.0 if the color has no alpha channel
+    pub fn alpha(&self) -> f64 {
+        C::alpha(self)
+    }
+
     pub fn map(mut self, f: impl Fn(f64) -> f64) -> Pixel<C> {
         for i in 0..self.len() {
             self[i] = f(self[i]);
@@ -128,20 +197,53 @@ impl<C: Color> Pixel<C> {
     }
 
     pub fn iter(&self) -> std::slice::Iter<f64> {
-        self.0.iter()
+        self.as_ref().iter()
     }
 
     pub fn iter_mut(&mut self) -> std::slice::IterMut<f64> {
-        self.0.iter_mut()
+        self.as_mut().iter_mut()
+    }
+}
+
+impl<C: Premultiply> Pixel<C> {
+    /// Multiply color channels by alpha
+    pub fn premultiply(&self) -> Pixel<C::Premultiplied> {
+        let alpha = self.alpha();
+        let mut px = Pixel::new();
+        for i in 0..self.len() {
+            px[i] = if self.is_alpha(i) {
+                alpha
+            } else {
+                self[i] * alpha
+            };
+        }
+        px
+    }
+}
+
+impl<C: Unpremultiply> Pixel<C> {
+    /// Divide color channels by alpha
+    pub fn unpremultiply(&self) -> Pixel<C::Straight> {
+        let alpha = self.alpha();
+        let mut px = Pixel::new();
+        for i in 0..self.len() {
+            px[i] = if self.is_alpha(i) {
+                alpha
+            } else {
+                color::unpremultiply(self[i], alpha)
+            };
+        }
+        px
     }
 }
 
 impl<T: Type, C: Color> std::iter::FromIterator<T> for Pixel<C> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        Pixel(
-            iter.into_iter().map(|x| x.to_norm()).collect(),
-            std::marker::PhantomData,
-        )
+        let mut px = Pixel::new();
+        for (a, x) in px.iter_mut().zip(iter) {
+            *a = x.to_norm();
+        }
+        px
     }
 }
 
@@ -150,40 +252,40 @@ impl<C: Color> IntoIterator for Pixel<C> {
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.to_vec().into_iter()
+        self.into_vec().into_iter()
     }
 }
 
 impl<C: Color> std::ops::Index<usize> for Pixel<C> {
     type Output = f64;
     fn index(&self, index: usize) -> &f64 {
-        &self.0[index]
+        &self.as_ref()[index]
     }
 }
 
 impl<'a, C: Color> std::ops::Index<usize> for &'a Pixel<C> {
     type Output = f64;
     fn index(&self, index: usize) -> &f64 {
-        &self.0[index]
+        &self.as_ref()[index]
     }
 }
 
 impl<'a, C: Color> std::ops::Index<usize> for &'a mut Pixel<C> {
     type Output = f64;
     fn index(&self, index: usize) -> &f64 {
-        &self.0[index]
+        &self.as_ref()[index]
     }
 }
 
 impl<C: Color> std::ops::IndexMut<usize> for Pixel<C> {
     fn index_mut(&mut self, index: usize) -> &mut f64 {
-        &mut self.0[index]
+        &mut self.as_mut()[index]
     }
 }
 
 impl<'a, C: Color> std::ops::IndexMut<usize> for &'a mut Pixel<C> {
     fn index_mut(&mut self, index: usize) -> &mut f64 {
-        &mut self.0[index]
+        &mut self.as_mut()[index]
     }
 }
 
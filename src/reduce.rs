@@ -0,0 +1,99 @@
+use crate::*;
+
+/// Reductions compute a single value from the pixels of an image, see `Image::reduce`
+///
+/// Each part of the image is added to its own accumulator created with `init`, partial results
+/// are then combined using `merge`, which allows reductions to run in parallel
+pub trait Reduce<T: Type, C: Color>: Sync {
+    /// Result of the reduction
+    type Output: Send;
+
+    /// Create an empty accumulator
+    fn init(&self) -> Self::Output;
+
+    /// Add the pixel at (x, y) to an accumulator
+    fn add(&self, acc: &mut Self::Output, x: usize, y: usize, px: &[T]);
+
+    /// Combine two partial results, pixels in `a` come before pixels in `b` in row-major order
+    fn merge(&self, a: Self::Output, b: Self::Output) -> Self::Output;
+}
+
+/// Per-channel histograms with the given number of bins, see `Image::histogram`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeHistogram {
+    pub bins: usize,
+}
+
+impl<T: Type, C: Color> Reduce<T, C> for ComputeHistogram {
+    type Output = Vec<Histogram>;
+
+    fn init(&self) -> Vec<Histogram> {
+        vec![Histogram::new(self.bins); C::CHANNELS]
+    }
+
+    fn add(&self, acc: &mut Vec<Histogram>, _x: usize, _y: usize, px: &[T]) {
+        for (hist, value) in acc.iter_mut().zip(px) {
+            hist.add_value(*value);
+        }
+    }
+
+    fn merge(&self, a: Vec<Histogram>, b: Vec<Histogram>) -> Vec<Histogram> {
+        a.into_iter()
+            .zip(b)
+            .map(|(a, b)| Histogram::join([a, b]))
+            .collect()
+    }
+}
+
+/// Per-channel statistics, optionally limited to the pixels set in a mask. See `Image::stats`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComputeStats<'a> {
+    pub mask: Option<&'a Bitmap>,
+}
+
+impl<'a, T: Type, C: Color> Reduce<T, C> for ComputeStats<'a> {
+    type Output = Vec<StatsAccumulator>;
+
+    fn init(&self) -> Self::Output {
+        vec![StatsAccumulator::default(); C::CHANNELS]
+    }
+
+    fn add(&self, acc: &mut Self::Output, x: usize, y: usize, px: &[T]) {
+        if self.mask.is_some_and(|mask| !mask.get(x, y)) {
+            return;
+        }
+
+        for (a, value) in acc.iter_mut().zip(px) {
+            a.add(value.to_norm(), (x, y));
+        }
+    }
+
+    fn merge(&self, a: Self::Output, b: Self::Output) -> Self::Output {
+        a.into_iter().zip(b).map(|(a, b)| a.merge(b)).collect()
+    }
+}
+
+/// Sets one bit for every pixel of a 16x8 image with an average value over 0.5, used by
+/// `Image::hash`
+#[cfg(feature = "transforms")]
+pub(crate) struct ComputeHash;
+
+#[cfg(feature = "transforms")]
+impl<T: Type, C: Color> Reduce<T, C> for ComputeHash {
+    type Output = u128;
+
+    fn init(&self) -> u128 {
+        0
+    }
+
+    fn add(&self, acc: &mut u128, x: usize, y: usize, px: &[T]) {
+        let avg: f64 = px.iter().map(|x| x.to_norm()).sum::<f64>() / C::CHANNELS as f64;
+        if avg > 0.5 {
+            *acc |= 1 << (y * 16 + x);
+        }
+    }
+
+    fn merge(&self, a: u128, b: u128) -> u128 {
+        a | b
+    }
+}
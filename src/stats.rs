@@ -0,0 +1,155 @@
+/// Statistics for a single image channel, see `Image::stats`
+///
+/// All values are normalized. NaN and infinite values are counted but don't contribute to any of
+/// the other fields
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// Smallest value, NaN if there are no finite values
+    pub min: f64,
+
+    /// Position of the first pixel containing `min`
+    pub min_position: (usize, usize),
+
+    /// Largest value, NaN if there are no finite values
+    pub max: f64,
+
+    /// Position of the first pixel containing `max`
+    pub max_position: (usize, usize),
+
+    /// Sum of all values
+    pub sum: f64,
+
+    /// Mean value, NaN if there are no finite values
+    pub mean: f64,
+
+    /// Population variance, NaN if there are no finite values
+    pub variance: f64,
+
+    /// Number of finite values
+    pub count: usize,
+
+    /// Number of NaN values
+    pub nan: usize,
+
+    /// Number of infinite values
+    pub infinite: usize,
+}
+
+impl Stats {
+    /// Population standard deviation
+    pub fn std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+}
+
+/// Running statistics for a single channel, produced by `reduce::ComputeStats`. Partial results
+/// can be merged to compute statistics in parallel
+#[derive(Debug, Clone, Copy)]
+pub struct StatsAccumulator {
+    min: (f64, (usize, usize)),
+    max: (f64, (usize, usize)),
+    sum: f64,
+    mean: f64,
+    m2: f64,
+    count: usize,
+    nan: usize,
+    infinite: usize,
+}
+
+impl Default for StatsAccumulator {
+    fn default() -> StatsAccumulator {
+        StatsAccumulator {
+            min: (f64::INFINITY, (0, 0)),
+            max: (f64::NEG_INFINITY, (0, 0)),
+            sum: 0.0,
+            mean: 0.0,
+            m2: 0.0,
+            count: 0,
+            nan: 0,
+            infinite: 0,
+        }
+    }
+}
+
+/// Pick the extreme value, ties go to the first pixel in row-major order so the result doesn't
+/// depend on how the work was split
+fn pick(
+    a: (f64, (usize, usize)),
+    b: (f64, (usize, usize)),
+    better: impl Fn(f64, f64) -> bool,
+) -> (f64, (usize, usize)) {
+    let (pa, pb) = ((a.1 .1, a.1 .0), (b.1 .1, b.1 .0));
+    if better(b.0, a.0) || (b.0 == a.0 && pb < pa) {
+        b
+    } else {
+        a
+    }
+}
+
+impl StatsAccumulator {
+    /// Add a single value
+    pub fn add(&mut self, x: f64, position: (usize, usize)) {
+        if x.is_nan() {
+            self.nan += 1;
+            return;
+        } else if x.is_infinite() {
+            self.infinite += 1;
+            return;
+        }
+
+        self.min = pick(self.min, (x, position), |a, b| a < b);
+        self.max = pick(self.max, (x, position), |a, b| a > b);
+        self.sum += x;
+        self.count += 1;
+
+        // Welford's algorithm
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Combine two partial results
+    pub fn merge(self, other: StatsAccumulator) -> StatsAccumulator {
+        let count = self.count + other.count;
+        let (mean, m2) = if count == 0 {
+            (0.0, 0.0)
+        } else {
+            let (a, b) = (self.count as f64, other.count as f64);
+            let delta = other.mean - self.mean;
+            (
+                self.mean + delta * b / count as f64,
+                self.m2 + other.m2 + delta * delta * a * b / count as f64,
+            )
+        };
+
+        StatsAccumulator {
+            min: pick(self.min, other.min, |a, b| a < b),
+            max: pick(self.max, other.max, |a, b| a > b),
+            sum: self.sum + other.sum,
+            mean,
+            m2,
+            count,
+            nan: self.nan + other.nan,
+            infinite: self.infinite + other.infinite,
+        }
+    }
+
+    /// Get the final statistics
+    pub fn finish(self) -> Stats {
+        let empty = self.count == 0;
+        let or_nan = |x: f64| if empty { f64::NAN } else { x };
+        Stats {
+            min: or_nan(self.min.0),
+            min_position: self.min.1,
+            max: or_nan(self.max.0),
+            max_position: self.max.1,
+            sum: self.sum,
+            mean: or_nan(self.mean),
+            variance: or_nan(self.m2 / self.count as f64),
+            count: self.count,
+            nan: self.nan,
+            infinite: self.infinite,
+        }
+    }
+}
@@ -28,6 +28,66 @@ fn test_image_buffer_new() {
     Invert.eval(&mut dest, &[&image]);
 }
 
+#[test]
+fn test_pixel() {
+    let a: Pixel<Rgb> = Pixel::from_slice(&[0.1, 0.2, 0.3]);
+    let b = a.clone() * 2.0;
+    assert_eq!(a.len(), 3);
+    assert_eq!(a.iter().count(), 3);
+    assert!((b[2] - 0.6).abs() < 1e-9);
+    assert!(a < b);
+    assert_eq!((b - a.clone()).into_vec().len(), 3);
+    assert_eq!(a.clone().map2(&a, |x, y| x - y), Pixel::new());
+    assert_eq!(
+        [0u8, 255, 0].iter().copied().collect::<Pixel<Rgb>>()[1],
+        1.0
+    );
+
+    // Colors with more channels than fit inline are stored on the heap
+    let mut px: Pixel<Channels<6>> = Pixel::new().fill(1.0f32);
+    px[5] = 0.5;
+    px += 1.0f32;
+    assert_eq!(px.as_ref(), &[2.0, 2.0, 2.0, 2.0, 2.0, 1.5]);
+    assert_eq!(px.clone(), px);
+}
+
+#[test]
+fn test_type_conversion() {
+    // Signed types use their full range
+    assert_eq!(i8::from_norm(0.0), i8::MIN);
+    assert_eq!(i8::from_norm(1.0), i8::MAX);
+    assert_eq!(i16::from_norm(i16::MIN.to_norm()), i16::MIN);
+    assert_eq!(i8::from_norm(128.0 / 255.0), 0);
+    assert_eq!(5u8.convert::<i8>(), -123);
+
+    // Rounding
+    assert_eq!(u8::from_norm(0.5), 128);
+    assert_eq!(u8::from_norm_rounded(0.5, Rounding::Truncate), 127);
+    assert_eq!(u16::from_f64(2.5), 3);
+    assert_eq!(f32::from_f64_rounded(0.25, Rounding::Truncate), 0.25);
+
+    // Out of range values saturate instead of wrapping
+    assert_eq!(u8::from_norm(1.5), 255);
+    assert_eq!(u8::from_norm(-0.5), 0);
+    assert_eq!(i16::from_f64(1e9), i16::MAX);
+    assert_eq!(u64::from_norm(2.0), u64::MAX);
+    assert!(u8::is_clipped(1.01) && !u8::is_clipped(1.0) && !f32::is_clipped(2.0));
+
+    let mut image: Image<f32, Rgb> = Image::new(2, 1);
+    image.set(0, 0, [0.5, 1.5, -0.25]);
+    image.set(1, 0, [0.2, 0.4, 0.6]);
+    let (dest, clipped) = image.convert_checked::<u8, Rgb>(Rounding::Nearest);
+    assert_eq!(clipped, 2);
+    assert_eq!(dest.get(0, 0), &[128, 255, 0]);
+    assert_eq!(dest, image.convert());
+    assert_eq!(
+        image
+            .convert_rounded::<u8, Rgb>(Rounding::Truncate)
+            .get(0, 0),
+        &[127, 255, 0]
+    );
+}
+
 #[test]
 fn test_read_write() {
     let a: Image<u8, Rgb> = Image::open("images/A.exr").unwrap();
@@ -81,6 +141,60 @@ fn test_invert_async() {
     assert!(dest.save("images/test-invert-async.jpg").is_ok());
 }
 
+#[test]
+fn test_compute_pixel() {
+    // Filters that compute whole pixels give the same result as computing each channel
+    fn check(filter: &(impl Filter + Unpin), image: &Image<f32, Rgba>) {
+        let mut expected = image.new_like();
+        expected.for_each(|(x, y), px| {
+            for (c, f) in px.iter_mut().enumerate() {
+                *f = filter.compute_at(x, y, c, &[image]) as f32;
+            }
+        });
+
+        let mut dest = image.new_like();
+        filter.eval(&mut dest, &[image]);
+        for (a, b) in dest.data.iter().zip(expected.data.iter()) {
+            assert!((a - b).abs() < 1e-6);
+        }
+
+        let mut dest = image.new_like();
+        smol::block_on(filter::eval_async(
+            filter,
+            filter::AsyncMode::Pixel,
+            &mut dest,
+            &[image],
+        ));
+        for (a, b) in dest.data.iter().zip(expected.data.iter()) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    let mut image: Image<f32, Rgba> = Image::new(8, 6);
+    image.for_each(|(x, y), px| {
+        px.copy_from_slice(&[
+            x as f32 / 8.0,
+            y as f32 / 6.0,
+            0.5,
+            ((x + y) % 3) as f32 / 2.0,
+        ]);
+    });
+
+    let kernel = kernel::gaussian_3x3();
+    check(&kernel, &image);
+    check(&Convert::<Rgba>::new(), &image);
+    check(
+        &kernel.join::<Rgba, Rgba, Kernel, Invert, _>(&Invert, |_, a, b| a - b),
+        &image,
+    );
+    check(
+        &Invert.and_then::<Rgba, _>(|(_, _, c), f| f * c as f64),
+        &image,
+    );
+    #[cfg(feature = "transforms")]
+    check(&transform::scale(2.0, 1.5), &image);
+}
+
 #[cfg(feature = "transforms")]
 #[test]
 fn test_hash() {
@@ -155,3 +269,992 @@ fn test_type_and_color_name() {
     assert!(f32::type_name() != f64::type_name());
     assert!(u8::type_name() == u8::type_name());
 }
+
+#[test]
+fn test_lossless_orientation() {
+    use io::exif::Orientation;
+
+    let mut image: Image<u8, Gray> = Image::new(3, 2);
+    let mut n = 0;
+    image.each_pixel_mut(|_, px| {
+        px[0] = n;
+        n += 1;
+    });
+
+    // 0 1 2
+    // 3 4 5
+    let r90 = image.rotate90();
+    assert_eq!(r90.shape(), (2, 3, 1));
+    assert_eq!(r90.data, vec![3, 0, 4, 1, 5, 2]);
+    assert_eq!(image.rotate270().data, vec![2, 5, 1, 4, 0, 3]);
+    assert_eq!(image.rotate180().data, vec![5, 4, 3, 2, 1, 0]);
+    assert_eq!(image.flip_horizontal().data, vec![2, 1, 0, 5, 4, 3]);
+    assert_eq!(image.flip_vertical().data, vec![3, 4, 5, 0, 1, 2]);
+    assert_eq!(image.transpose().data, vec![0, 3, 1, 4, 2, 5]);
+    assert_eq!(image.transverse().data, vec![5, 2, 4, 1, 3, 0]);
+
+    assert_eq!(r90.orient(Orientation::Rotate270), image);
+    assert_eq!(image.transpose().orient(Orientation::Transpose), image);
+    assert_eq!(image.transverse().orient(Orientation::Transverse), image);
+}
+
+#[test]
+fn test_data_window() {
+    let mut image: Image<f32, Gray> = Image::new(3, 2);
+    let mut n = 0.0;
+    image.each_pixel_mut(|_, px| {
+        px[0] = n;
+        n += 0.1;
+    });
+    image.meta.x = 2;
+    image.meta.y = 1;
+    image.meta.full_width = 8;
+    image.meta.full_height = 4;
+    image.meta.pixel_aspect = io::exif::Rational::new(2, 1);
+    assert!(image.meta.has_display_window());
+
+    let crop = image.crop(Region::new(1, 0, 2, 2));
+    assert_eq!(crop.meta.data_window(), (3, 1, 2, 2));
+    assert_eq!(crop.meta.display_window(), (0, 0, 8, 4));
+    assert_eq!(crop.get(0, 1), image.get(1, 1));
+
+    let r90 = image.rotate90();
+    assert_eq!(r90.meta.data_window(), (1, 2, 2, 3));
+    assert_eq!(r90.meta.display_window(), (0, 0, 4, 8));
+    assert_eq!(r90.meta.pixel_aspect, io::exif::Rational::new(1, 2));
+    assert_eq!(r90.rotate270(), image);
+    assert_eq!(image.flip_horizontal().meta.data_window(), (3, 1, 3, 2));
+    assert_eq!(image.flip_vertical().meta.data_window(), (2, 1, 3, 2));
+
+    let mut full: Image<f32, Gray> = Image::new(8, 4);
+    full.copy_window(&image);
+    assert_eq!(full.get(2, 1), image.get(0, 0));
+    assert_eq!(full.get(4, 2), image.get(2, 1));
+    assert_eq!(full.get(1, 1), &[0.0]);
+
+    #[cfg(feature = "transforms")]
+    {
+        let mut dest: Image<f32, Gray> = Image::new(8, 4);
+        transform::resize(&image, 8, 4).eval(&mut dest, &[&image]);
+        assert_eq!(dest, full);
+    }
+}
+
+#[test]
+fn test_multispectral() {
+    let mut image: Image<u16, Channels<8>> = Image::new(4, 3);
+    image.for_each(|(x, y), px| {
+        for (c, v) in px.iter_mut().enumerate() {
+            *v = (c * 1000 + y * 10 + x) as u16;
+        }
+    });
+    assert_eq!(image.channels(), 8);
+    assert_eq!(image.get_pixel(1, 2).len(), 8);
+    assert_eq!(image.histogram(16).len(), 8);
+
+    let rgb = image.select_bands::<Rgb>(&[4, 2, 1]).unwrap();
+    assert_eq!(rgb.get(3, 1), &[4013, 2013, 1013]);
+    assert!(matches!(
+        image.select_bands::<Rgb>(&[4, 2]),
+        Err(Error::InvalidBandCount(3, 2))
+    ));
+    assert!(matches!(image.band(8), Err(Error::InvalidChannel(8))));
+
+    let bands: Vec<_> = (0..8).map(|c| image.band(c).unwrap()).collect();
+    let merged = Image::<u16, Channels<8>>::merge_bands(&bands.iter().collect::<Vec<_>>());
+    assert_eq!(merged.unwrap(), image);
+    assert!(matches!(
+        Image::<u16, Rgb>::merge_bands(&[&bands[0], &bands[1]]),
+        Err(Error::InvalidBandCount(3, 2))
+    ));
+    let small = Image::new(2, 2);
+    assert!(matches!(
+        Image::<u16, Rgb>::merge_bands(&[&bands[0], &bands[1], &small]),
+        Err(Error::InvalidDimensions(2, 2, 1))
+    ));
+
+    let converted: Image<f32, Rgb> = image.convert();
+    assert!((converted.get_f(1, 0, 2) - image.get_f(1, 0, 2)).abs() < 1e-6);
+
+    let mut inverted = image.new_like();
+    filter::Invert.eval(&mut inverted, &[&image]);
+    assert_eq!(inverted.get(0, 0)[7], u16::MAX - 7000);
+}
+
+#[test]
+fn test_alpha() {
+    assert!(Rgba::ALPHA && !Rgba::PREMULTIPLIED);
+    assert!(PremultipliedRgba::ALPHA && PremultipliedRgba::PREMULTIPLIED);
+
+    let mut image: Image<f32, Rgba> = Image::new(3, 1);
+    image.set(0, 0, [1.0, 0.0, 0.0, 0.0]);
+    image.set(1, 0, [0.0, 1.0, 0.0, 1.0]);
+    image.set(2, 0, [0.0, 0.0, 1.0, 0.5]);
+
+    let rgb: Image<f32, Rgb> = image.convert();
+    assert_eq!(rgb.get(2, 0), &[0.0, 0.0, 1.0]);
+
+    let gray: Image<f32, GrayAlpha> = image.convert();
+    assert_eq!(gray.get(2, 0)[1], 0.5);
+
+    let premultiplied = image.premultiply();
+    assert_eq!(premultiplied.get(0, 0), &[0.0, 0.0, 0.0, 0.0]);
+    assert_eq!(premultiplied.get(2, 0), &[0.0, 0.0, 0.5, 0.5]);
+    assert_eq!(
+        premultiplied.get_pixel(2, 0),
+        image.get_pixel(2, 0).premultiply()
+    );
+    assert_eq!(
+        premultiplied.convert::<f32, Rgba>().get(2, 0),
+        image.get(2, 0)
+    );
+    assert_eq!(premultiplied.unpremultiply().get(1, 0), image.get(1, 0));
+
+    // The transparent red pixel shouldn't bleed into the opaque green pixel
+    let mut blur = image.new_like();
+    let mut kernel = Kernel::from([[0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [0.0, 0.0, 0.0]]);
+    kernel.normalize();
+    kernel.eval(&mut blur, &[&image]);
+    let px = blur.get(1, 0);
+    assert_eq!(px[0], 0.0);
+    assert!(px[1] > px[2]);
+    assert!((px[3] - 0.5).abs() < 1e-6);
+
+    // Kernels that aren't a weighted average give the same color as without alpha
+    let mut image: Image<f32, Rgba> = Image::new(3, 3);
+    image.for_each(|(x, _), px| px.copy_from_slice(&[0.2, 0.2, 0.2, 0.5 + x as f32 * 1e-4]));
+    let rgb: Image<f32, Rgb> = image.convert();
+    let double = Kernel::from([[0.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 0.0]]);
+    let (mut a, mut b) = (image.new_like(), rgb.new_like());
+    double.eval(&mut a, &[&image]);
+    double.eval(&mut b, &[&rgb]);
+    assert!((a.get(1, 1)[0] - 0.4).abs() < 1e-6);
+    assert_eq!(a.get(1, 1)[0], b.get(1, 1)[0]);
+
+    let mut edges = image.new_like();
+    kernel::sobel_x().eval(&mut edges, &[&image]);
+    assert!(edges.get(1, 1)[0].abs() < 1e-6);
+}
+
+#[test]
+fn test_channel_order() {
+    let mut image: Image<f32, Rgba> = Image::new(2, 1);
+    image.set(0, 0, [1.0, 0.5, 0.25, 1.0]);
+    image.set(1, 0, [0.0, 0.25, 0.5, 0.5]);
+
+    let bgra: Image<f32, Bgra> = image.convert();
+    assert_eq!(bgra.get(0, 0), &[0.25, 0.5, 1.0, 1.0]);
+    assert_eq!(bgra.meta.alpha_channel(), Some(3));
+    assert_eq!(bgra.convert::<f32, Rgba>(), image);
+
+    let argb: Image<f32, Argb> = image.convert();
+    assert_eq!(argb.get(1, 0), &[0.5, 0.0, 0.25, 0.5]);
+    assert_eq!(argb.meta.alpha_channel(), Some(0));
+    assert_eq!(argb.convert::<f32, Bgra>(), bgra);
+
+    let bgr: Image<f32, Bgr> = image.convert();
+    assert_eq!(bgr.get(0, 0), &[0.25, 0.5, 1.0]);
+    assert_eq!(bgr.meta.alpha_channel(), None);
+
+    // Files store channels in RGBA order
+    let disk = argb.disk_channel_order();
+    assert_eq!(disk.get(1, 0), image.get(1, 0));
+    let mut argb2 = disk.into_owned();
+    argb2.restore_channel_order();
+    assert_eq!(argb2, argb);
+
+    // Wrap a BGRA buffer, like those used by most windowing systems
+    let data: Vec<u8> = vec![0, 0, 255, 255, 255, 0, 0, 255, 0, 0, 0, 0];
+    let view = ImageView::<u8, Bgra>::new(&data, 0, 2, 1, 12).unwrap();
+    let rgb: Image<u8, Rgb> = view.to_image().convert();
+    assert_eq!(rgb.get(0, 0), &[255, 0, 0]);
+    assert_eq!(rgb.get(1, 0), &[0, 0, 255]);
+}
+
+#[test]
+fn test_lab() {
+    fn close(a: &[f64], b: &[f64], epsilon: f64) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < epsilon)
+    }
+
+    let mut image: Image<f64, Rgb> = Image::new(4, 1);
+    image.set(0, 0, [1.0, 1.0, 1.0]);
+    image.set(1, 0, [1.0, 0.0, 0.0]);
+    image.set(2, 0, [0.2, 0.5, 0.8]);
+    image.set(3, 0, [0.0, 0.0, 0.0]);
+
+    // Published values for sRGB red
+    let lab: Image<f64, Lab> = image.convert();
+    let px = lab.get(1, 0);
+    let px = [px[0] * 100.0, px[1] * 255.0 - 128.0, px[2] * 255.0 - 128.0];
+    assert!(close(&px, &[53.2408, 80.0925, 67.2032], 1e-2));
+    assert!(close(
+        lab.get(0, 0),
+        &[1.0, 128.0 / 255.0, 128.0 / 255.0],
+        1e-4
+    ));
+
+    let lab: Image<f64, Lab<D50>> = image.convert();
+    let px = lab.get(1, 0);
+    let px = [px[0] * 100.0, px[1] * 255.0 - 128.0, px[2] * 255.0 - 128.0];
+    assert!(close(&px, &[54.29, 80.80, 69.89], 5e-2));
+    assert!(close(
+        lab.get(0, 0),
+        &[1.0, 128.0 / 255.0, 128.0 / 255.0],
+        1e-4
+    ));
+
+    let luv: Image<f64, Luv> = image.convert();
+    let px = luv.get(1, 0);
+    let px = [px[0] * 100.0, px[1] * 354.0 - 134.0, px[2] * 262.0 - 140.0];
+    assert!(close(&px, &[53.2408, 175.0151, 37.7564], 1e-2));
+
+    let lch: Image<f64, Lch> = image.convert();
+    let px = lch.get(1, 0);
+    assert!(close(
+        &[px[1] * 150.0, px[2] * 360.0],
+        &[104.5518, 39.9990],
+        1e-2
+    ));
+
+    assert!(close(&lab.convert::<f64, Rgb>().data, &image.data, 1e-6));
+    assert!(close(&luv.convert::<f64, Rgb>().data, &image.data, 1e-6));
+    assert!(close(&lch.convert::<f64, Rgb>().data, &image.data, 1e-6));
+
+    // Encoded mid-gray, primaries alone can't tell encoded and linear values apart
+    let mut gray: Image<f64, Srgb> = Image::new(1, 1);
+    gray.set(0, 0, [0.5, 0.5, 0.5]);
+    let lab: Image<f64, Lab> = gray.convert();
+    assert!(close(
+        lab.get(0, 0),
+        &[0.533889, 128.0 / 255.0, 128.0 / 255.0],
+        1e-5
+    ));
+    let oklab: Image<f64, Oklab> = gray.convert();
+    assert!((oklab.get(0, 0)[0] - 0.598181).abs() < 1e-5);
+}
+
+#[test]
+fn test_oklab_hsl() {
+    fn close(a: &[f64], b: &[f64], epsilon: f64) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < epsilon)
+    }
+
+    // Reference values from https://bottosson.github.io/posts/oklab/
+    let mut image: Image<f64, Rgb> = Image::new(5, 1);
+    image.set(0, 0, [1.0, 1.0, 1.0]);
+    image.set(1, 0, [1.0, 0.0, 0.0]);
+    image.set(2, 0, [0.0, 1.0, 0.0]);
+    image.set(3, 0, [0.0, 0.0, 1.0]);
+    image.set(4, 0, [0.2, 0.5, 0.8]);
+
+    let oklab: Image<f64, Oklab> = image.convert();
+    let unnormalize = |px: &[f64]| [px[0], px[1] - 0.5, px[2] - 0.5];
+    assert!(close(&unnormalize(oklab.get(0, 0)), &[1.0, 0.0, 0.0], 1e-4));
+    let red = [0.627955, 0.224863, 0.125846];
+    assert!(close(&unnormalize(oklab.get(1, 0)), &red, 1e-4));
+    let green = [0.866440, -0.233888, 0.179498];
+    assert!(close(&unnormalize(oklab.get(2, 0)), &green, 1e-4));
+    let blue = [0.452014, -0.032457, -0.311528];
+    assert!(close(&unnormalize(oklab.get(3, 0)), &blue, 1e-4));
+
+    let oklch: Image<f64, Oklch> = image.convert();
+    let px = oklch.get(1, 0);
+    assert!(close(
+        &[px[0], px[1] * 0.4, px[2] * 360.0],
+        &[0.627955, 0.257683, 29.2339],
+        1e-3
+    ));
+
+    let hsl: Image<f64, Hsl> = image.convert();
+    assert!(close(hsl.get(1, 0), &[0.0, 1.0, 0.5], 1e-9));
+    assert!(close(hsl.get(2, 0), &[1.0 / 3.0, 1.0, 0.5], 1e-9));
+    assert!(close(hsl.get(4, 0), &[210.0 / 360.0, 0.6, 0.5], 1e-9));
+    assert!(close(hsl.get(0, 0), &[0.0, 0.0, 1.0], 1e-9));
+
+    assert!(close(&oklab.convert::<f64, Rgb>().data, &image.data, 1e-6));
+    assert!(close(&oklch.convert::<f64, Rgb>().data, &image.data, 1e-6));
+    assert!(close(&hsl.convert::<f64, Rgb>().data, &image.data, 1e-6));
+
+    // Every 8-bit RGB value on a coarse grid round-trips through 16-bit storage
+    let mut grid: Image<u8, Rgb> = Image::new(18, 18 * 18);
+    grid.each_pixel_mut(|(x, y), px| {
+        px[0] = (x * 15) as u8;
+        px[1] = (y % 18 * 15) as u8;
+        px[2] = (y / 18 * 15) as u8;
+    });
+
+    fn roundtrip<C: Color>(grid: &Image<u8, Rgb>) {
+        let converted: Image<u16, C> = grid.convert();
+        let back: Image<u8, Rgb> = converted.convert();
+        for (a, b) in back.data.iter().zip(grid.data.iter()) {
+            assert!(
+                (*a as i32 - *b as i32).abs() <= 1,
+                "{}: {} != {}",
+                C::NAME,
+                a,
+                b
+            );
+        }
+    }
+
+    roundtrip::<Oklab>(&grid);
+    roundtrip::<Oklch>(&grid);
+    roundtrip::<Hsl>(&grid);
+}
+
+#[test]
+fn test_ycbcr() {
+    fn to_u8<C: Color>(image: &Image<f64, Rgb>) -> Vec<u8> {
+        let ycbcr: Image<f64, C> = image.convert();
+        let back: Image<f64, Rgb> = ycbcr.convert();
+        for (a, b) in back.data.iter().zip(image.data.iter()) {
+            assert!((a - b).abs() < 1e-9);
+        }
+        ycbcr
+            .data
+            .iter()
+            .map(|x| (x * 255.0).round() as u8)
+            .collect()
+    }
+
+    let mut image: Image<f64, Rgb> = Image::new(5, 1);
+    image.set(0, 0, [1.0, 1.0, 1.0]);
+    image.set(1, 0, [0.0, 0.0, 0.0]);
+    image.set(2, 0, [1.0, 0.0, 0.0]);
+    image.set(3, 0, [0.0, 1.0, 0.0]);
+    image.set(4, 0, [0.0, 0.0, 1.0]);
+
+    // Published 8-bit values for white, black, red, green and blue
+    assert_eq!(
+        to_u8::<YCbCr>(&image),
+        [255, 128, 128, 0, 128, 128, 76, 85, 255, 150, 44, 21, 29, 255, 107]
+    );
+    assert_eq!(
+        to_u8::<YCbCr<Bt601, LimitedRange>>(&image),
+        [235, 128, 128, 16, 128, 128, 81, 90, 240, 145, 54, 34, 41, 240, 110]
+    );
+    assert_eq!(
+        to_u8::<YCbCr<Bt709, LimitedRange>>(&image),
+        [235, 128, 128, 16, 128, 128, 63, 102, 240, 173, 42, 26, 32, 240, 118]
+    );
+    assert_eq!(
+        to_u8::<YCbCr<Bt2020, LimitedRange>>(&image),
+        [235, 128, 128, 16, 128, 128, 74, 97, 240, 164, 47, 25, 29, 240, 119]
+    );
+    assert_eq!(
+        to_u8::<YCbCr<Bt709, FullRange>>(&image),
+        [255, 128, 128, 0, 128, 128, 54, 99, 255, 182, 30, 12, 18, 255, 116]
+    );
+}
+
+#[test]
+fn test_color_roundtrip() {
+    // Largest difference between an RGB image and the same image converted to `C` and back,
+    // measured in normalized units, colors without chroma are only checked with gray input.
+    // 8-bit images go through a float intermediate and must come back within one level, the
+    // 16-bit tolerance accounts for quantization of the intermediate image
+    fn roundtrip<T: Type, C: Color>(gray: bool) -> f64 {
+        roundtrip_via::<T, T, C>(gray)
+    }
+
+    fn roundtrip_via<T: Type, U: Type, C: Color>(gray: bool) -> f64 {
+        let mut image: Image<T, Rgb> = Image::new(16, 16 * 16);
+        image.for_each(|(x, y), px| {
+            let (r, g, b) = (x, y % 16, y / 16);
+            let (g, b) = if gray { (r, r) } else { (g, b) };
+            px[0] = T::from_norm(r as f64 / 15.0);
+            px[1] = T::from_norm(g as f64 / 15.0);
+            px[2] = T::from_norm(b as f64 / 15.0);
+        });
+
+        let converted: Image<U, C> = image.convert();
+        let back: Image<T, Rgb> = converted.convert();
+        back.data
+            .iter()
+            .zip(image.data.iter())
+            .map(|(a, b)| (a.to_norm() - b.to_norm()).abs())
+            .fold(0.0, f64::max)
+    }
+
+    macro_rules! check {
+        ($($c:ty: $gray:expr),*$(,)?) => {
+            $(
+                let name = stringify!($c);
+                assert!(roundtrip_via::<u8, f32, $c>($gray) <= 1.0 / 255.0, "{} u8", name);
+                assert!(roundtrip::<u16, $c>($gray) < 5e-3, "{} u16", name);
+                assert!(roundtrip::<f32, $c>($gray) < 1e-5, "{} f32", name);
+            )*
+        };
+    }
+
+    check!(
+        Gray: true,
+        GrayAlpha: true,
+        PremultipliedGrayAlpha: true,
+        Channels<1>: true,
+        Rgb: false,
+        Srgb: false,
+        LinearRgb: false,
+        Rec2020: false,
+        Rec2100Pq: false,
+        Rec2100Hlg: false,
+        Rgba: false,
+        PremultipliedRgba: false,
+        Bgr: false,
+        Bgra: false,
+        Argb: false,
+        Channels<3>: false,
+        Channels<5>: false,
+        Xyz: false,
+        Lab: false,
+        Lab<D50>: false,
+        Lch: false,
+        Luv: false,
+        Oklab: false,
+        Oklch: false,
+        Hsv: false,
+        Hsl: false,
+        Yuv: false,
+        YCbCr: false,
+        YCbCr<Bt709, LimitedRange>: false,
+        YCbCr<Bt2020, FullRange>: false,
+        Cmyk: false,
+    );
+}
+
+#[test]
+fn test_convert_pixel() {
+    // Whole pixel conversions, including the matrix path, match the per-channel conversion
+    fn check<C: Color, D: Color>(values: &[f64]) {
+        let src: Pixel<C> = Pixel::from_slice(values);
+        let mut dest = Pixel::<D>::new();
+        C::convert_pixel(&src, &mut dest);
+        for c in 0..D::CHANNELS {
+            assert!((dest[c] - C::convert::<D>(c, &src)).abs() < 1e-9);
+        }
+
+        if let Some(m) = color::conversion_matrix::<C, D>() {
+            let mut direct = Pixel::<D>::new();
+            color::convert_pixel_matrix(&m, &src, &mut direct);
+            for c in 0..D::CHANNELS {
+                assert!((direct[c] - dest[c]).abs() < 1e-9);
+            }
+        }
+    }
+
+    check::<Rgb, Lab>(&[0.2, 0.5, 0.9]);
+    check::<Lab, Hsl>(&[0.5, 0.3, 0.6]);
+    check::<Rgba, Bgra>(&[0.2, 0.5, 0.9, 0.5]);
+    check::<Xyz, Rec2020>(&[0.3, 0.4, 0.2]);
+    check::<Argb, PremultipliedRgba>(&[0.5, 0.2, 0.5, 0.9]);
+    check::<Bgr, Cmyk>(&[0.2, 0.5, 0.9]);
+    check::<YCbCr<Bt709, LimitedRange>, Oklch>(&[0.5, 0.4, 0.6]);
+
+    // Converting part of an image only touches that region
+    let mut image: Image<f32, Rgb> = Image::new(4, 4);
+    image.for_each(|_, px| px.copy_from_slice(&[1.0; 3]));
+    let mut dest: Image<f32, Xyz> = Image::new(4, 4);
+    Convert::<Xyz>::new().eval_partial::<f32, f32, Rgb, Xyz>(
+        Region::new(0, 0, 2, 4),
+        &mut dest,
+        &[&image],
+    );
+    assert!((dest.get(1, 3)[1] - 1.0 / (65535.0 / 32768.0)).abs() < 1e-6);
+    assert_eq!(dest.get(2, 3), &[0.0; 3]);
+}
+
+#[test]
+fn test_transfer_function() {
+    for transfer in [
+        TransferFunction::Linear,
+        TransferFunction::Srgb,
+        TransferFunction::Rec709,
+        TransferFunction::Gamma(2.2),
+    ]
+    .iter()
+    {
+        for i in -10..=20 {
+            let x = i as f64 / 10.0;
+            assert!((transfer.decode(transfer.encode(x)) - x).abs() < 1e-9);
+        }
+    }
+    assert!((TransferFunction::Srgb.decode(0.5) - 0.214041).abs() < 1e-6);
+    assert!((TransferFunction::Rec709.encode(0.5) - 0.705515).abs() < 1e-6);
+
+    // Conversions linearize sRGB data
+    let mut image: Image<f32, Srgb> = Image::new(4, 1);
+    image.set(1, 0, [0.5, 0.5, 0.5]);
+    image.set(3, 0, [1.0, 1.0, 1.0]);
+    let linear: Image<f32, LinearRgb> = image.convert();
+    assert!((linear.get(1, 0)[0] - 0.214041).abs() < 1e-6);
+
+    // Only `Srgb` and `LinearRgb` are converted from the transfer function of the file, other
+    // colors keep the stored values so 8-bit files round-trip exactly
+    assert_eq!(io::magick::kind::<Srgb>().unwrap(), "rgb:-");
+    assert_eq!(io::magick::kind::<LinearRgb>().unwrap(), "rgb:-");
+    assert_eq!(io::file_transfer("EXR"), TransferFunction::Linear);
+    assert_eq!(io::file_transfer("png"), TransferFunction::Srgb);
+    let mut file: Image<u8, Rgb> = Image::new(256, 1);
+    file.for_each(|(x, _), px| px.iter_mut().for_each(|v| *v = x as u8));
+    let mut rgb = file.clone();
+    io::decode_file_transfer(&mut rgb, TransferFunction::Srgb);
+    assert_eq!(rgb, file);
+    assert_eq!(
+        *io::encode_file_transfer(&rgb, TransferFunction::Srgb),
+        file
+    );
+    let mut linear: Image<u8, LinearRgb> = Image::new(256, 1);
+    linear.data.copy_from_slice(file.data());
+    io::decode_file_transfer(&mut linear, TransferFunction::Srgb);
+    assert_eq!(linear.get(128, 0), &[55, 55, 55]);
+    let mut srgb: Image<f32, Srgb> = Image::new(1, 1);
+    srgb.set(0, 0, [0.5, 0.5, 0.5]);
+    io::decode_file_transfer(&mut srgb, TransferFunction::Srgb);
+    assert_eq!(srgb.get(0, 0), &[0.5, 0.5, 0.5]);
+    let exr = io::encode_file_transfer(&srgb, TransferFunction::Linear);
+    assert!((exr.get(0, 0)[0] - 0.214041).abs() < 1e-6);
+    io::decode_file_transfer(&mut srgb, TransferFunction::Linear);
+    assert!((srgb.get(0, 0)[0] - 0.735357).abs() < 1e-5);
+
+    // Blurring black next to white gives 50% linear light, not 50% of the encoded value
+    let mut blur = image.new_like();
+    let kernel = Kernel::from([[0.0, 0.0, 0.0], [0.0, 0.5, 0.5], [0.0, 0.0, 0.0]]);
+    kernel.eval(&mut blur, &[&image]);
+    assert!((blur.get(2, 0)[0] - 0.735357).abs() < 1e-5);
+
+    // Gamma works on normalized values and leaves alpha alone
+    let mut image: Image<u8, Rgba> = Image::new(1, 1);
+    image.set(0, 0, [64, 128, 255, 128]);
+    image.gamma(2.2);
+    assert_eq!(image.get(0, 0), &[136, 186, 255, 128]);
+
+    image.linearize(TransferFunction::Gamma(2.2));
+    let px = image.get(0, 0);
+    assert!((px[1] as i32 - 128).abs() <= 1 && px[3] == 128);
+}
+
+#[test]
+fn test_hdr() {
+    // Published PQ code values for 100, 1000 and 10000 cd/m²
+    let pq = TransferFunction::Pq(10000.0);
+    assert!((pq.encode(0.01) - 0.508078).abs() < 1e-5);
+    assert!((pq.encode(0.1) - 0.751827).abs() < 1e-5);
+    assert!((pq.encode(1.0) - 1.0).abs() < 1e-9);
+    assert!((TransferFunction::Pq(1000.0).encode(1.0) - 0.751827).abs() < 1e-5);
+
+    let hlg = TransferFunction::Hlg;
+    assert!((hlg.encode(1.0 / 12.0) - 0.5).abs() < 1e-9);
+    assert!((hlg.encode(1.0) - 1.0).abs() < 1e-6);
+
+    for transfer in [pq, TransferFunction::Pq(1000.0), hlg].iter() {
+        for i in 0..=20 {
+            let x = i as f64 / 20.0;
+            assert!((transfer.decode(transfer.encode(x)) - x).abs() < 1e-9);
+        }
+    }
+
+    // Encode a linear BT.709 image as 16-bit HDR10 and back
+    let mut image: Image<f32, Rgb> = Image::new(2, 1);
+    image.set(0, 0, [0.25, 0.5, 0.75]);
+    image.set(1, 0, [1.0, 0.0, 0.0]);
+    let rec2020: Image<f32, Rec2020> = image.convert();
+    let mut hdr10: Image<u16, Rec2100Pq> = rec2020.new_like_with_type_and_color();
+    filter::Encode(TransferFunction::Pq(10000.0)).eval(&mut hdr10, &[&rec2020]);
+    let mut decoded = rec2020.new_like();
+    filter::Decode(TransferFunction::Pq(10000.0)).eval(&mut decoded, &[&hdr10]);
+    let back: Image<f32, Rgb> = decoded.convert();
+    for (a, b) in back.data.iter().zip(image.data.iter()) {
+        assert!((a - b).abs() < 1e-3);
+    }
+
+    // The encoded color type decodes PQ when converting, unlike linear `Rec2020`
+    let converted: Image<u16, Rec2100Pq> = image.convert();
+    assert_eq!(converted, hdr10);
+    let back: Image<f32, Rgb> = hdr10.convert();
+    for (a, b) in back.data.iter().zip(image.data.iter()) {
+        assert!((a - b).abs() < 1e-3);
+    }
+
+    // Both backends store BT.2020 colors as RGB code values without converting them
+    assert_eq!(io::magick::kind::<Rec2020>().unwrap(), "rgb:-");
+    assert_eq!(io::magick::kind::<Rec2100Pq>().unwrap(), "rgb:-");
+    assert_eq!(io::magick::kind::<Rec2100Hlg>().unwrap(), "rgb:-");
+    let mut stored = hdr10.clone();
+    io::decode_file_transfer(&mut stored, io::file_transfer("png"));
+    assert_eq!(stored, hdr10);
+
+    // HLG reference white is displayed at peak luminance, mid gray depends on the system gamma
+    let mut hlg_image: Image<f32, Rec2020> = Image::new(2, 1);
+    hlg_image.set(0, 0, [1.0, 1.0, 1.0]);
+    hlg_image.set(1, 0, [0.5, 0.5, 0.5]);
+    let mut display = hlg_image.new_like();
+    filter::HlgEotf(1000.0).eval(&mut display, &[&hlg_image]);
+    assert!((display.get(0, 0)[0] - 1.0).abs() < 1e-6);
+    assert!((display.get(1, 0)[0] as f64 - (1.0f64 / 12.0).powf(1.2)).abs() < 1e-6);
+
+    let mut signal = hlg_image.new_like();
+    filter::HlgInverseEotf(1000.0).eval(&mut signal, &[&display]);
+    for (a, b) in signal.data.iter().zip(hlg_image.data.iter()) {
+        assert!((a - b).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn test_colorspace() {
+    use colorspace::{Colorspace, Conversion};
+
+    fn close(a: &[f64], b: &[f64], epsilon: f64) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < epsilon)
+    }
+
+    assert_eq!(
+        Colorspace::find("ACES2065-1"),
+        Colorspace::find("aces_2065_1")
+    );
+    assert_eq!(Colorspace::find("lnf").unwrap().name, "linear sRGB");
+    assert!(matches!(
+        Conversion::named("srgb", "not a colorspace"),
+        Err(Error::UnknownColorspace(_))
+    ));
+
+    // Published linear sRGB to ACEScg matrix, using Bradford adaptation
+    let aces = Conversion::named("linear", "ACEScg").unwrap();
+    assert!(close(
+        &aces.convert([1.0, 0.0, 0.0]),
+        &[0.6130974, 0.0701937, 0.0206156],
+        1e-4
+    ));
+    assert!(close(
+        &aces.convert([1.0, 1.0, 1.0]),
+        &[1.0, 1.0, 1.0],
+        1e-6
+    ));
+
+    let p3 = Conversion::named("sRGB", "Display P3").unwrap();
+    assert!(close(
+        &p3.convert([1.0, 0.0, 0.0]),
+        &[0.9175, 0.2003, 0.1386],
+        1e-3
+    ));
+
+    for a in colorspace::COLORSPACES {
+        for b in colorspace::COLORSPACES {
+            let there = Conversion::new(a, b);
+            let back = Conversion::new(b, a);
+            let rgb = [0.2, 0.5, 0.8];
+            assert!(close(&back.convert(there.convert(rgb)), &rgb, 1e-6));
+        }
+    }
+
+    let mut image: Image<f32, Rgba> = Image::new(1, 1);
+    image.set(0, 0, [0.5, 0.5, 0.5, 0.25]);
+    let linear = image.convert_colorspace("srgb", "linear").unwrap();
+    assert!((linear.get(0, 0)[0] - 0.214041).abs() < 1e-6);
+    assert_eq!(linear.get(0, 0)[3], 0.25);
+    assert!(image.convert_colorspace("srgb", "unknown").is_err());
+}
+
+#[test]
+fn test_bitmap() {
+    let mut a = Bitmap::new(70, 3);
+    let mut b = Bitmap::new(70, 3);
+    a.set(0, 0, true);
+    a.set(65, 1, true);
+    b.set(65, 1, true);
+    b.set(69, 2, true);
+
+    assert_eq!((&a & &b).count_ones(), 1);
+    assert_eq!((&a | &b).count_ones(), 3);
+    assert_eq!((&a ^ &b).count_ones(), 2);
+    assert_eq!((!&a).count_ones(), 70 * 3 - 2);
+    assert_eq!((!&a).count_zeros(), 2);
+    assert!((&a ^ &a).data().iter().all(|x| *x == 0));
+
+    let mut c = a.clone();
+    c ^= &b;
+    assert_eq!(c.count_ones(), 2);
+    c ^= &b;
+    assert_eq!(c, a);
+    c |= &b;
+    c &= &a;
+    assert_eq!(c, a);
+
+    let mut image: Image<u8, Gray> = Image::new(4, 2);
+    image.set(1, 0, [200]);
+    image.set(3, 1, [128]);
+    image.set(0, 1, [127]);
+    let bitmap = Bitmap::threshold(&image, 0.5);
+    assert_eq!(bitmap.count_ones(), 2);
+    assert!(bitmap.get(1, 0) && bitmap.get(3, 1) && !bitmap.get(0, 1));
+
+    let gray: Image<u8, Gray> = bitmap.to_image();
+    assert_eq!(gray.get(1, 0)[0], 255);
+    assert_eq!(gray.get(0, 1)[0], 0);
+    assert_eq!(Bitmap::threshold(&gray, 0.5), bitmap);
+
+    // PBM uses 1 for black
+    let plain = Bitmap::decode(b"P1\n# comment\n3 2\n0 1 0\n1 1 0").unwrap();
+    assert_eq!(plain.shape(), (3, 2));
+    assert_eq!(plain.count_ones(), 3);
+    assert!(plain.get(0, 0) && !plain.get(1, 0) && plain.get(2, 1));
+
+    let pbm = io::pbm::encode(&a);
+    assert!(pbm.starts_with(b"P4\n70 3\n"));
+    assert_eq!(pbm.len(), 8 + 9 * 3);
+    assert_eq!(Bitmap::decode(&pbm).unwrap(), a);
+    assert_eq!(Bitmap::decode(&io::g4::encode_tiff(&a)).unwrap(), a);
+    assert!(Bitmap::decode(b"P4\n8 8\n").is_err());
+
+    // Malformed headers are rejected before allocating
+    assert!(Bitmap::decode(b"P4\n4000000000 4000000000\n\0\0\0").is_err());
+    assert!(Bitmap::decode(b"P1\n4000000000 4000000000\n0 1").is_err());
+    assert!(Bitmap::decode(b"P4\n99999999999999999999 1\n").is_err());
+
+    let dir = std::env::temp_dir();
+    assert!(a.save(dir.join("test-bitmap.pbm")).is_ok());
+    assert_eq!(Bitmap::open(dir.join("test-bitmap.pbm")).unwrap(), a);
+    assert!(b.save(dir.join("test-bitmap.tif")).is_ok());
+    assert_eq!(Bitmap::open(dir.join("test-bitmap.tif")).unwrap(), b);
+    assert!(b.save(dir.join("test-bitmap.png")).is_err());
+}
+
+#[test]
+fn test_image_ops() {
+    let mut a: Image<u8, Gray> = Image::new(2, 1);
+    a.set(0, 0, [200]);
+    a.set(1, 0, [10]);
+    let mut b: Image<u8, Gray> = Image::new(2, 1);
+    b.set(0, 0, [100]);
+    b.set(1, 0, [100]);
+
+    // Integer results saturate
+    assert_eq!((&a + &b).data(), &[255, 110]);
+    assert_eq!((&a - &b).data(), &[100, 0]);
+    assert_eq!((&a * 2).data(), &[255, 20]);
+    assert_eq!((&a / 4).data(), &[50, 2]);
+    assert_eq!((&a / &b).data(), &[2, 0]);
+    let f: Image<f32, Gray> = a.convert();
+    assert!(((&f / 4.0).data()[1] - 10.0 / 255.0 / 4.0).abs() < 1e-6);
+    assert_eq!((&a % 3).data(), &[2, 1]);
+
+    // Division by zero saturates instead of panicking, 0 / 0 and remainders by zero are 0
+    assert_eq!((&a / 0).data(), &[255, 255]);
+    assert_eq!((&a % 0).data(), &[0, 0]);
+    assert_eq!((&(&a * 0) / 0).data(), &[0, 0]);
+    let mut s: Image<i8, Gray> = Image::new(2, 1);
+    s.set(0, 0, [-10]);
+    s.set(1, 0, [10]);
+    assert_eq!((&s / 0).data(), &[-128, 127]);
+
+    // Pixels are normalized
+    let half: Pixel<Gray> = Pixel::from_slice(&[0.5]);
+    assert_eq!((&a * half).data(), &[100, 5]);
+
+    let mut c = a.clone() + b.clone();
+    c -= &b;
+    c -= 5;
+    assert_eq!(c.data(), &[150, 5]);
+    assert!(c.zip_values(&Image::new(1, 1), |a, _| a).is_err());
+
+    let f: Image<f32, Gray> = a.convert();
+    let g = &f * &f - 0.5f32;
+    assert!((g.get(0, 0)[0] - (f.get(0, 0)[0].powi(2) - 0.5)).abs() < 1e-6);
+}
+
+#[test]
+#[should_panic(expected = "Image dimensions don't match")]
+fn test_image_ops_shape() {
+    let a: Image<u8, Gray> = Image::new(2, 1);
+    let _ = &a + &Image::new(1, 2);
+}
+
+#[test]
+fn test_stats() {
+    let mut image: Image<f32, GrayAlpha> = Image::new(4, 3);
+    image.for_each(|(x, y), px| {
+        px[0] = (x + y * 4) as f32 / 11.0;
+        px[1] = 1.0;
+    });
+    image.set(3, 2, [f32::NAN, 1.0]);
+    image.set(2, 2, [f32::INFINITY, 1.0]);
+
+    let stats = image.stats();
+    assert_eq!(stats.len(), 2);
+    let s = stats[0];
+    assert_eq!((s.count, s.nan, s.infinite), (10, 1, 1));
+    assert_eq!((s.min, s.min_position), (0.0, (0, 0)));
+    assert!((s.max - 9.0 / 11.0).abs() < 1e-6);
+    assert_eq!(s.max_position, (1, 2));
+    assert!((s.mean - 4.5 / 11.0).abs() < 1e-6);
+    assert!((s.variance - 8.25 / 121.0).abs() < 1e-6);
+    assert!((s.sum - 45.0 / 11.0).abs() < 1e-6);
+    assert_eq!(
+        (stats[1].min, stats[1].max, stats[1].std_dev()),
+        (1.0, 1.0, 0.0)
+    );
+
+    // Ties go to the first pixel
+    assert_eq!(stats[1].max_position, (0, 0));
+
+    let s = image.stats_region(Region::new(1, 1, 2, 1))[0];
+    assert_eq!(s.count, 2);
+    assert!((s.mean - 5.5 / 11.0).abs() < 1e-6);
+
+    let mut mask = Bitmap::new(4, 3);
+    mask.set(3, 0, true);
+    assert!((image.stats_masked(&mask)[0].mean - 3.0 / 11.0).abs() < 1e-6);
+    assert!(image.stats_masked(&Bitmap::new(4, 3))[0].mean.is_nan());
+
+    let mut image: Image<u8, Gray> = Image::new(5, 1);
+    image.for_each(|(x, _), px| px[0] = 255 - x as u8 * 10);
+    assert_eq!(image.max(), ((0, 0), Pixel::from_slice(&[1.0])));
+    assert_eq!(image.min().0, (4, 0));
+    assert!((image.percentile(50.0)[0] - 235.0 / 255.0).abs() < 1e-9);
+    assert!((image.percentile(12.5)[0] - 220.0 / 255.0).abs() < 1e-9);
+    assert_eq!(image.percentile(100.0)[0], 1.0);
+}
+
+#[test]
+fn test_reduce() {
+    struct CountColors;
+
+    impl Reduce<u8, Rgb> for CountColors {
+        type Output = std::collections::BTreeMap<[u8; 3], usize>;
+
+        fn init(&self) -> Self::Output {
+            Default::default()
+        }
+
+        fn add(&self, acc: &mut Self::Output, _x: usize, _y: usize, px: &[u8]) {
+            *acc.entry([px[0], px[1], px[2]]).or_default() += 1;
+        }
+
+        fn merge(&self, mut a: Self::Output, b: Self::Output) -> Self::Output {
+            for (k, v) in b {
+                *a.entry(k).or_default() += v;
+            }
+            a
+        }
+    }
+
+    let mut image: Image<u8, Rgb> = Image::new(64, 48);
+    image.for_each(|(x, _), px| px[0] = (x / 16) as u8);
+    let colors = image.reduce(&CountColors);
+    assert_eq!(colors.len(), 4);
+    assert!(colors.values().all(|n| *n == 16 * 48));
+
+    let colors = image.reduce_region(Region::new(60, 40, 100, 100), &CountColors);
+    assert_eq!(colors.get(&[3, 0, 0]), Some(&(4 * 8)));
+
+    let hist = image.reduce(&reduce::ComputeHistogram { bins: 256 });
+    assert_eq!(hist, image.histogram(256));
+    assert_eq!(hist[0].bin(3), 16 * 48);
+    assert_eq!(hist[1].bin(0), 64 * 48);
+}
+
+#[test]
+fn test_equalize() {
+    // Four equally common values are spread over the whole range
+    let mut image: Image<u8, GrayAlpha> = Image::new(4, 4);
+    image.for_each(|(x, _), px| {
+        px[0] = 100 + x as u8;
+        px[1] = 128;
+    });
+
+    let mut dest = image.new_like();
+    Equalize::new(&image.histogram(256)).eval(&mut dest, &[&image]);
+    assert_eq!(
+        dest.row(0).iter().step_by(2).copied().collect::<Vec<_>>(),
+        [0, 85, 170, 255]
+    );
+    assert!(dest.row(3).iter().skip(1).step_by(2).all(|x| *x == 128));
+
+    // A single value is left as is
+    let flat: Image<u8, Gray> = Image::new(2, 2);
+    let mut dest = flat.new_like();
+    Equalize::new(&flat.histogram(256)).eval(&mut dest, &[&flat]);
+    assert_eq!(dest, flat);
+
+    // Floating point values outside of 0..=1 are counted in the end bins and left unchanged
+    let mut hdr: Image<f32, Gray> = Image::new(4, 1);
+    hdr.data.copy_from_slice(&[0.25, 0.5, 2.0, -1.0]);
+    let hist = hdr.histogram(256);
+    assert_eq!((hist[0][0], hist[0][255]), (1, 1));
+    let mut dest = hdr.new_like();
+    Equalize::new(&hist).eval(&mut dest, &[&hdr]);
+    assert_eq!((dest.data[2], dest.data[3]), (2.0, -1.0));
+    assert!(dest.data[0] < dest.data[1] && dest.data[1] <= 1.0);
+}
+
+#[test]
+fn test_match_histogram() {
+    let mut image: Image<u8, Gray> = Image::new(8, 8);
+    image.for_each(|(x, _), px| px[0] = 10 + (x / 2) as u8 * 10);
+    let mut reference: Image<u8, Gray> = Image::new(4, 4);
+    reference.for_each(|(_, y), px| px[0] = 100 + y as u8 * 50);
+
+    let mut dest = image.new_like();
+    MatchHistogram::new(&image.histogram(256), &reference.histogram(256))
+        .eval(&mut dest, &[&image]);
+    assert_eq!(dest.row(0), &[100, 100, 150, 150, 200, 200, 250, 250]);
+    assert_eq!(
+        dest.histogram(256)[0].distribution(),
+        reference.histogram(256)[0].distribution()
+    );
+}
+
+#[test]
+fn test_clahe() {
+    let mut image: Image<u8, Gray> = Image::new(64, 64);
+    image.for_each(|(x, y), px| px[0] = 100 + ((x + y) / 4) as u8);
+    let spread = |image: &Image<u8, Gray>| {
+        let stats = image.stats()[0];
+        stats.max - stats.min
+    };
+
+    let equalized = image.clahe((4, 4), 0.0);
+    let clipped = image.clahe((4, 4), 1.5);
+    assert!(spread(&equalized) > 0.5);
+    assert!(spread(&clipped) > spread(&image));
+    assert!(spread(&clipped) < spread(&equalized));
+
+    // Tiles smaller than the number of bins still respect the clip limit
+    assert_ne!(image.clahe((8, 8), 1.5), image.clahe((8, 8), 50.0));
+
+    // Color images are equalized by lightness, neutral colors stay neutral and alpha is kept
+    let mut rgba: Image<f32, Rgba> = Image::new(32, 32);
+    rgba.for_each(|(x, _), px| {
+        let v = 0.4 + x as f32 / 320.0;
+        px.copy_from_slice(&[v, v, v, 0.5]);
+    });
+    let dest = rgba.clahe((2, 2), 0.0);
+    let stats = dest.stats();
+    assert!(stats[0].max - stats[0].min > rgba.stats()[0].max - rgba.stats()[0].min);
+    assert_eq!((stats[3].min, stats[3].max), (0.5, 0.5));
+    let px = dest.get(10, 10);
+    assert!((px[0] - px[1]).abs() < 1e-3 && (px[1] - px[2]).abs() < 1e-3);
+
+    // Other colors only equalize the first channel, the remaining channels are untouched
+    let mut bands: Image<f32, Channels<4>> = Image::new(32, 32);
+    bands.for_each(|(x, y), px| {
+        px.copy_from_slice(&[0.4 + x as f32 / 320.0, 0.1, y as f32 / 32.0, 0.9]);
+    });
+    let dest = bands.clahe((2, 2), 0.0);
+    assert!(dest.stats()[0].max - dest.stats()[0].min > 0.5);
+    dest.iter()
+        .zip(bands.iter())
+        .for_each(|((_, a), (_, b))| assert_eq!(a[1..], b[1..]));
+    let mut cmyk: Image<u8, Cmyk> = Image::new(16, 16);
+    cmyk.for_each(|(x, y), px| px.copy_from_slice(&[100 + x as u8, 20, 30 + y as u8, 200]));
+    let dest = cmyk.clahe((2, 2), 0.0);
+    dest.iter()
+        .zip(cmyk.iter())
+        .for_each(|((_, a), (_, b))| assert_eq!(a[1..], b[1..]));
+
+    // Empty images have nothing to equalize
+    let empty: Image<u8, Gray> = Image::new(0, 0);
+    assert_eq!(empty.clahe((4, 4), 2.0).shape(), (0, 0, 1));
+    let empty: Image<f32, Rgb> = Image::new(0, 3);
+    assert_eq!(empty.clahe((4, 4), 2.0).shape(), (0, 3, 3));
+}
@@ -0,0 +1,128 @@
+/// Transfer function used to encode linear light values
+///
+/// All functions operate on normalized values. Negative values are mirrored by the SDR curves so
+/// extended range floating point data survives a round trip, the HDR curves clamp them to 0
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TransferFunction {
+    /// Values are already linear
+    #[default]
+    Linear,
+
+    /// IEC 61966-2-1 sRGB curve
+    Srgb,
+
+    /// ITU-R BT.709 camera curve, also used by BT.2020
+    Rec709,
+
+    /// Pure power curve, encoded values are `linear ^ (1 / gamma)`
+    Gamma(f64),
+
+    /// SMPTE ST 2084 perceptual quantizer used by HDR10, linear 1.0 is the given peak luminance
+    /// in cd/m²
+    Pq(f64),
+
+    /// ITU-R BT.2100 hybrid log-gamma OETF, operating on scene linear values in `0..=1`. See
+    /// `filter::HlgEotf` for the display side
+    Hlg,
+}
+
+/// Peak luminance of the PQ signal range in cd/m²
+pub const PQ_MAX_LUMINANCE: f64 = 10000.0;
+
+const PQ_M1: f64 = 2610.0 / 16384.0;
+const PQ_M2: f64 = 2523.0 / 4096.0 * 128.0;
+const PQ_C1: f64 = 3424.0 / 4096.0;
+const PQ_C2: f64 = 2413.0 / 4096.0 * 32.0;
+const PQ_C3: f64 = 2392.0 / 4096.0 * 32.0;
+
+const HLG_A: f64 = 0.17883277;
+const HLG_B: f64 = 1.0 - 4.0 * HLG_A;
+const HLG_C: f64 = 0.55991073;
+
+#[inline]
+fn mirror(x: f64, f: impl Fn(f64) -> f64) -> f64 {
+    if x < 0.0 {
+        -f(-x)
+    } else {
+        f(x)
+    }
+}
+
+impl TransferFunction {
+    /// Returns true if the function is the identity
+    pub fn is_linear(&self) -> bool {
+        match self {
+            TransferFunction::Linear => true,
+            TransferFunction::Gamma(g) => *g == 1.0,
+            _ => false,
+        }
+    }
+
+    /// Convert an encoded value to linear light
+    pub fn decode(&self, x: f64) -> f64 {
+        match self {
+            TransferFunction::Linear => x,
+            TransferFunction::Srgb => mirror(x, |x| {
+                if x <= 0.04045 {
+                    x / 12.92
+                } else {
+                    ((x + 0.055) / 1.055).powf(2.4)
+                }
+            }),
+            TransferFunction::Rec709 => mirror(x, |x| {
+                if x < 0.081 {
+                    x / 4.5
+                } else {
+                    ((x + 0.099) / 1.099).powf(1.0 / 0.45)
+                }
+            }),
+            TransferFunction::Gamma(g) => mirror(x, |x| x.powf(*g)),
+            TransferFunction::Pq(peak) => {
+                let n = x.max(0.0).powf(1.0 / PQ_M2);
+                let y = ((n - PQ_C1).max(0.0) / (PQ_C2 - PQ_C3 * n)).powf(1.0 / PQ_M1);
+                y * PQ_MAX_LUMINANCE / peak
+            }
+            TransferFunction::Hlg => {
+                if x <= 0.5 {
+                    x * x / 3.0
+                } else {
+                    (((x - HLG_C) / HLG_A).exp() + HLG_B) / 12.0
+                }
+            }
+        }
+    }
+
+    /// Encode a linear light value
+    pub fn encode(&self, x: f64) -> f64 {
+        match self {
+            TransferFunction::Linear => x,
+            TransferFunction::Srgb => mirror(x, |x| {
+                if x <= 0.0031308 {
+                    x * 12.92
+                } else {
+                    1.055 * x.powf(1.0 / 2.4) - 0.055
+                }
+            }),
+            TransferFunction::Rec709 => mirror(x, |x| {
+                if x < 0.018 {
+                    x * 4.5
+                } else {
+                    1.099 * x.powf(0.45) - 0.099
+                }
+            }),
+            TransferFunction::Gamma(g) => mirror(x, |x| x.powf(1.0 / *g)),
+            TransferFunction::Pq(peak) => {
+                let y = (x * peak / PQ_MAX_LUMINANCE).max(0.0).powf(PQ_M1);
+                ((PQ_C1 + PQ_C2 * y) / (1.0 + PQ_C3 * y)).powf(PQ_M2)
+            }
+            TransferFunction::Hlg => {
+                let x = x.max(0.0);
+                if x <= 1.0 / 12.0 {
+                    (3.0 * x).sqrt()
+                } else {
+                    HLG_A * (12.0 * x - HLG_B).ln() + HLG_C
+                }
+            }
+        }
+    }
+}
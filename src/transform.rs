@@ -4,22 +4,62 @@ use crate::*;
 pub type Point<T> = euclid::Point2D<T, T>;
 pub struct Transform(pub euclid::Transform2D<f64, f64, f64>);
 
+/// Get a normalized value from an image using coordinates relative to the display window,
+/// returning 0 outside of the data window
+fn display_f(image: &impl GenericImage, x: f64, y: f64, c: usize) -> f64 {
+    let x = x as isize + image.meta().full_x - image.meta().x;
+    let y = y as isize + image.meta().full_y - image.meta().y;
+    if x < 0 || y < 0 {
+        return 0.0;
+    }
+    image.get_f(x as usize, y as usize, c)
+}
+
+/// Transforms operate on display window coordinates: the output image is expected to cover the
+/// display window of the input and the data window offset of the input is taken into account
+/// when sampling
 impl Filter for Transform {
-    fn compute_at(
+    fn compute_at(&self, x: usize, y: usize, c: usize, input: &[&impl GenericImage]) -> f64 {
+        let pt = self.0.transform_point(Point::new(x as f64, y as f64));
+        sample(input[0], pt, c)
+    }
+
+    fn compute_pixel<C: Color>(
         &self,
         x: usize,
         y: usize,
-        c: usize,
-        input: &[&Image<impl Type, impl Color>],
-    ) -> f64 {
-        let pt = Point::new(x as f64, y as f64);
-        let dest = self.0.transform_point(pt);
-        (input[0].get_f(dest.x.floor() as usize, dest.y.floor() as usize, c)
-            + input[0].get_f(dest.x.ceil() as usize, dest.y.ceil() as usize, c))
-            / 2.
+        input: &[&impl GenericImage],
+        out: &mut Pixel<C>,
+    ) {
+        let pt = self.0.transform_point(Point::new(x as f64, y as f64));
+        for (c, f) in out.iter_mut().enumerate() {
+            *f = sample(input[0], pt, c);
+        }
     }
 }
 
+/// Interpolate channel `c` of `image` at `pt`
+fn sample(image: &impl GenericImage, pt: Point<f64>, c: usize) -> f64 {
+    let (x0, y0) = (pt.x.floor(), pt.y.floor());
+    let (x1, y1) = (pt.x.ceil(), pt.y.ceil());
+
+    // Color channels with straight alpha are weighted by alpha and channels with a
+    // non-linear transfer function are combined in linear light
+    let meta = image.meta();
+    let a = meta.decode_transfer(c, display_f(image, x0, y0, c));
+    let b = meta.decode_transfer(c, display_f(image, x1, y1, c));
+    let alpha = meta.alpha_channel().unwrap_or_default();
+    if meta.has_alpha() && !meta.is_premultiplied() && c != alpha {
+        let wa = display_f(image, x0, y0, alpha);
+        let wb = display_f(image, x1, y1, alpha);
+        if wa + wb > 0.0 {
+            return meta.encode_transfer(c, (a * wa + b * wb) / (wa + wb));
+        }
+    }
+
+    meta.encode_transfer(c, (a + b) / 2.)
+}
+
 #[inline]
 pub fn rotate(deg: f64, center: Point<f64>) -> Transform {
     Transform(
@@ -36,15 +76,16 @@ pub fn scale(x: f64, y: f64) -> Transform {
 
 #[inline]
 pub fn resize(src: &Image<impl Type, impl Color>, mut x: usize, mut y: usize) -> Transform {
+    let (_, _, width, height) = src.meta.display_window();
     if x == 0 {
-        y = x * src.height() / src.width()
+        y = x * height / width
     } else if y == 0 {
-        x = y * src.width() / src.height()
+        x = y * width / height
     }
 
     Transform(euclid::Transform2D::scale(
-        src.width() as f64 / x as f64,
-        src.height() as f64 / y as f64,
+        width as f64 / x as f64,
+        height as f64 / y as f64,
     ))
 }
 
@@ -53,13 +94,13 @@ pub fn rotate90(
     src: &Image<impl Type, impl Color>,
 ) -> Transform {
     let dwidth = dest.width() as f64;
-    let height = src.height() as f64;
+    let height = src.meta.full_height as f64;
     rotate(90., Point::new(dwidth / 2., height / 2.))
 }
 
 pub fn rotate180(src: &Image<impl Type, impl Color>) -> Transform {
-    let dwidth = src.width() as f64;
-    let height = src.height() as f64;
+    let dwidth = src.meta.full_width as f64;
+    let height = src.meta.full_height as f64;
     rotate(180., Point::new(dwidth / 2., height / 2.))
 }
 
@@ -68,7 +109,7 @@ pub fn rotate270(
     src: &Image<impl Type, impl Color>,
 ) -> Transform {
     let width = dest.height() as f64;
-    let dheight = src.width() as f64;
+    let dheight = src.meta.full_width as f64;
     rotate(270., Point::new(width / 2., dheight / 2.))
 }
 
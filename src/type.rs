@@ -1,5 +1,27 @@
 use crate::*;
 
+/// How values are rounded when converting to an integer type
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round to the nearest integer, halfway cases away from zero
+    #[default]
+    Nearest,
+
+    /// Round towards zero
+    Truncate,
+}
+
+impl Rounding {
+    /// Round `f` to an integer value
+    #[inline]
+    pub fn round(self, f: f64) -> f64 {
+        match self {
+            Rounding::Nearest => f.round(),
+            Rounding::Truncate => f.trunc(),
+        }
+    }
+}
+
 pub trait Type: Unpin + Default + Clone + Copy + Sync + Send + PartialEq + PartialOrd {
     const MIN: f64;
     const MAX: f64;
@@ -7,8 +29,19 @@ pub trait Type: Unpin + Default + Clone + Copy + Sync + Send + PartialEq + Parti
 
     fn to_f64(&self) -> f64;
 
+    /// Convert from `f64`, integer types round to the nearest value and saturate at `MIN` and
+    /// `MAX` instead of wrapping
     fn from_f64(f: f64) -> Self;
 
+    /// Convert from `f64` using the given rounding mode, floating point types ignore `rounding`
+    fn from_f64_rounded(f: f64, rounding: Rounding) -> Self {
+        if Self::is_float() {
+            Self::from_f64(f)
+        } else {
+            Self::from_f64(rounding.round(f))
+        }
+    }
+
     fn is_float() -> bool {
         let x = Self::to_f64(&Self::from_f64(0.5));
         x > 0.0 && x < 1.0
@@ -52,6 +85,17 @@ pub trait Type: Unpin + Default + Clone + Copy + Sync + Send + PartialEq + Parti
         Self::from_f64(Self::denormalize(f))
     }
 
+    /// Convert from a normalized value using the given rounding mode
+    fn from_norm_rounded(f: f64, rounding: Rounding) -> Self {
+        Self::from_f64_rounded(Self::denormalize(f), rounding)
+    }
+
+    /// Returns true when the normalized value `f` is outside the range of an integer type and
+    /// would be clipped by `from_norm`, floating point types keep values outside of `0..=1`
+    fn is_clipped(f: f64) -> bool {
+        !Self::is_float() && !(0.0..=1.0).contains(&f)
+    }
+
     #[inline]
     fn normalize(f: f64) -> f64 {
         (f - Self::MIN) / (Self::MAX - Self::MIN)
@@ -59,7 +103,7 @@ pub trait Type: Unpin + Default + Clone + Copy + Sync + Send + PartialEq + Parti
 
     #[inline]
     fn denormalize(f: f64) -> f64 {
-        f * Self::MAX - Self::MIN
+        f * (Self::MAX - Self::MIN) + Self::MIN
     }
 
     #[inline]
@@ -93,7 +137,7 @@ impl Type for u8 {
     }
 
     fn from_f64(f: f64) -> Self {
-        f as Self
+        <Self as Type>::clamp(f.round()) as Self
     }
 }
 
@@ -107,7 +151,7 @@ impl Type for i8 {
     }
 
     fn from_f64(f: f64) -> Self {
-        f as Self
+        <Self as Type>::clamp(f.round()) as Self
     }
 }
 
@@ -121,7 +165,7 @@ impl Type for u16 {
     }
 
     fn from_f64(f: f64) -> Self {
-        f as Self
+        <Self as Type>::clamp(f.round()) as Self
     }
 }
 
@@ -135,7 +179,7 @@ impl Type for i16 {
     }
 
     fn from_f64(f: f64) -> Self {
-        f as Self
+        <Self as Type>::clamp(f.round()) as Self
     }
 }
 
@@ -149,7 +193,7 @@ impl Type for u32 {
     }
 
     fn from_f64(f: f64) -> Self {
-        f as Self
+        <Self as Type>::clamp(f.round()) as Self
     }
 }
 
@@ -163,7 +207,7 @@ impl Type for i32 {
     }
 
     fn from_f64(f: f64) -> Self {
-        f as Self
+        <Self as Type>::clamp(f.round()) as Self
     }
 }
 
@@ -177,7 +221,7 @@ impl Type for u64 {
     }
 
     fn from_f64(f: f64) -> Self {
-        f as Self
+        <Self as Type>::clamp(f.round()) as Self
     }
 }
 
@@ -191,7 +235,7 @@ impl Type for i64 {
     }
 
     fn from_f64(f: f64) -> Self {
-        f as Self
+        <Self as Type>::clamp(f.round()) as Self
     }
 }
 
@@ -0,0 +1,453 @@
+use crate::*;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Read access to pixel data laid out in rows, implemented by `Image`, `ImageView` and
+/// `ImageViewMut` so they can be used interchangeably as `Filter` inputs
+pub trait GenericImage: Sync {
+    type T: Type;
+    type C: Color;
+
+    /// Metadata, `width` and `height` describe the visible area
+    fn meta(&self) -> &Meta<Self::T, Self::C>;
+
+    /// Number of elements between the start of two consecutive rows
+    fn stride(&self) -> usize;
+
+    /// Pixel data, starting at (0, 0)
+    fn data(&self) -> &[Self::T];
+
+    /// Image width
+    #[inline]
+    fn width(&self) -> usize {
+        self.meta().width
+    }
+
+    /// Image height
+    #[inline]
+    fn height(&self) -> usize {
+        self.meta().height
+    }
+
+    /// Returns the number of channels
+    #[inline]
+    fn channels(&self) -> usize {
+        Self::C::CHANNELS
+    }
+
+    /// Returns (width, height, channels)
+    #[inline]
+    fn shape(&self) -> (usize, usize, usize) {
+        (self.width(), self.height(), self.channels())
+    }
+
+    /// Get the index of the specified pixel
+    #[inline]
+    fn index(&self, x: usize, y: usize) -> usize {
+        self.stride() * y + x * self.channels()
+    }
+
+    /// Returns true when (x, y) is in bounds
+    #[inline]
+    fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width() && y < self.height()
+    }
+
+    /// Get data at specified index
+    #[inline]
+    fn get(&self, x: usize, y: usize) -> &[Self::T] {
+        let index = self.index(x, y);
+        &self.data()[index..index + self.channels()]
+    }
+
+    /// Get row
+    #[inline]
+    fn row(&self, y: usize) -> &[Self::T] {
+        let index = self.index(0, y);
+        &self.data()[index..index + self.channels() * self.width()]
+    }
+
+    /// Get a normalized float value
+    fn get_f(&self, x: usize, y: usize, c: usize) -> f64 {
+        if !self.in_bounds(x, y) || c >= self.channels() {
+            return 0.0;
+        }
+
+        self.get(x, y)[c].to_norm()
+    }
+
+    /// Load data into an existing `Pixel` structure
+    #[inline]
+    fn pixel_at(&self, x: usize, y: usize, px: &mut Pixel<Self::C>) -> bool {
+        if !self.in_bounds(x, y) {
+            return false;
+        }
+        px.copy_from_slice(self.get(x, y));
+        true
+    }
+
+    /// Get a normalized pixel
+    fn get_pixel(&self, x: usize, y: usize) -> Pixel<Self::C> {
+        let mut px = Pixel::new();
+        self.pixel_at(x, y, &mut px);
+        px
+    }
+
+    /// Get pixel iterator
+    fn iter(&self) -> impl Iterator<Item = ((usize, usize), &[Self::T])> {
+        let channels = self.channels();
+        (0..self.height()).flat_map(move |y| {
+            self.row(y)
+                .chunks_exact(channels)
+                .enumerate()
+                .map(move |(x, px)| ((x, y), px))
+        })
+    }
+
+    /// Get a view of part of an image, `roi` is clipped to the image bounds
+    fn view(&self, roi: Region) -> ImageView<'_, Self::T, Self::C> {
+        let roi = clip(roi, self.width(), self.height());
+        let start = self.index(roi.x, roi.y);
+        let end = start + extent(roi.width, roi.height, self.stride(), self.channels());
+        ImageView {
+            meta: region_meta(self.meta(), roi),
+            stride: self.stride(),
+            data: &self.data()[start..end],
+        }
+    }
+
+    /// Copy pixel data into a new image
+    fn to_image(&self) -> Image<Self::T, Self::C> {
+        let mut image = Image::new(self.width(), self.height());
+        image.meta = self.meta().clone();
+        for y in 0..self.height() {
+            image.row_mut(y).copy_from_slice(self.row(y));
+        }
+        image
+    }
+}
+
+/// Write access to pixel data laid out in rows, implemented by `Image` and `ImageViewMut` so
+/// they can be used interchangeably as `Filter` outputs
+pub trait GenericImageMut: GenericImage {
+    /// Mutable pixel data, starting at (0, 0)
+    fn data_mut(&mut self) -> &mut [Self::T];
+
+    /// Get mutable data at specified index
+    #[inline]
+    fn get_mut(&mut self, x: usize, y: usize) -> &mut [Self::T] {
+        let index = self.index(x, y);
+        let channels = self.channels();
+        &mut self.data_mut()[index..index + channels]
+    }
+
+    /// Get mutable row
+    #[inline]
+    fn row_mut(&mut self, y: usize) -> &mut [Self::T] {
+        let index = self.index(0, y);
+        let len = self.channels() * self.width();
+        &mut self.data_mut()[index..index + len]
+    }
+
+    /// Set data to specified location
+    #[inline]
+    fn set(&mut self, x: usize, y: usize, data: impl AsRef<[Self::T]>) {
+        self.get_mut(x, y).clone_from_slice(data.as_ref())
+    }
+
+    /// Set normalized float value
+    fn set_f(&mut self, x: usize, y: usize, c: usize, f: f64) {
+        if !self.in_bounds(x, y) || c >= self.channels() {
+            return;
+        }
+        self.get_mut(x, y)[c] = Self::T::from_norm(f);
+    }
+
+    /// Set a normalized pixel to the specified location
+    #[inline]
+    fn set_pixel(&mut self, x: usize, y: usize, px: &Pixel<Self::C>) {
+        px.copy_to_slice(self.get_mut(x, y));
+    }
+
+    /// Get mutable pixel iterator
+    fn iter_mut(&mut self) -> impl Iterator<Item = ((usize, usize), &mut [Self::T])> {
+        let (width, height, channels) = self.shape();
+        let stride = self.stride().max(1);
+        self.data_mut()
+            .chunks_mut(stride)
+            .take(height)
+            .enumerate()
+            .flat_map(move |(y, row)| {
+                row[..width * channels]
+                    .chunks_exact_mut(channels)
+                    .enumerate()
+                    .map(move |(x, px)| ((x, y), px))
+            })
+    }
+
+    /// Iterate over each pixel applying `f` to every pixel
+    fn for_each<F: Sync + Send + Fn((usize, usize), &mut [Self::T])>(&mut self, f: F) {
+        let (width, height, channels) = self.shape();
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let stride = self.stride();
+        let row = |(y, row): (usize, &mut [Self::T])| {
+            row[..width * channels]
+                .chunks_exact_mut(channels)
+                .enumerate()
+                .for_each(|(x, px)| f((x, y), px))
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            self.data_mut()
+                .par_chunks_mut(stride)
+                .take(height)
+                .enumerate()
+                .for_each(row)
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        {
+            self.data_mut()
+                .chunks_mut(stride)
+                .take(height)
+                .enumerate()
+                .for_each(row)
+        }
+    }
+
+    /// Iterate over a region of pixels applying `f` to every pixel
+    fn for_each_region<F: Sync + Send + Fn((usize, usize), &mut [Self::T])>(
+        &mut self,
+        roi: Region,
+        f: F,
+    ) {
+        let roi = clip(roi, self.width(), self.height());
+        self.view_mut(roi)
+            .for_each(|(x, y), px| f((x + roi.x, y + roi.y), px))
+    }
+
+    /// Get a mutable view of part of an image, `roi` is clipped to the image bounds
+    fn view_mut(&mut self, roi: Region) -> ImageViewMut<'_, Self::T, Self::C> {
+        let roi = clip(roi, self.width(), self.height());
+        let start = self.index(roi.x, roi.y);
+        let end = start + extent(roi.width, roi.height, self.stride(), self.channels());
+        ImageViewMut {
+            meta: region_meta(self.meta(), roi),
+            stride: self.stride(),
+            data: &mut self.data_mut()[start..end],
+        }
+    }
+}
+
+/// Clip a region to the given bounds
+fn clip(roi: Region, width: usize, height: usize) -> Region {
+    let x = roi.x.min(width);
+    let y = roi.y.min(height);
+    Region::new(x, y, roi.width.min(width - x), roi.height.min(height - y))
+}
+
+/// Number of elements spanned by `height` rows of `width` pixels
+fn extent(width: usize, height: usize, stride: usize, channels: usize) -> usize {
+    if width == 0 || height == 0 {
+        return 0;
+    }
+    stride * (height - 1) + width * channels
+}
+
+/// Metadata for a region of an image, the data window is offset like `Image::crop`
+fn region_meta<T: Type, C: Color>(meta: &Meta<T, C>, roi: Region) -> Meta<T, C> {
+    let mut dest = meta.with_geometry(roi.width, roi.height);
+    dest.x += roi.x as isize;
+    dest.y += roi.y as isize;
+    dest
+}
+
+/// Check that a buffer is big enough to hold an image with the given layout
+fn check_layout<C: Color>(
+    len: usize,
+    offset: usize,
+    width: usize,
+    height: usize,
+    stride: usize,
+) -> Result<(), Error> {
+    if stride < width * C::CHANNELS || offset + extent(width, height, stride, C::CHANNELS) > len {
+        return Err(Error::InvalidDimensions(width, height, C::CHANNELS));
+    }
+    Ok(())
+}
+
+/// Borrowed, read-only image data
+#[derive(Debug, Clone)]
+pub struct ImageView<'a, T: Type, C: Color> {
+    /// Metadata
+    pub meta: Meta<T, C>,
+    stride: usize,
+    data: &'a [T],
+}
+
+impl<'a, T: Type, C: Color> ImageView<'a, T, C> {
+    /// Create a view of an existing buffer, `offset` is the index of the first pixel and `stride`
+    /// is the number of elements between the start of two consecutive rows
+    pub fn new(
+        data: &'a [T],
+        offset: usize,
+        width: usize,
+        height: usize,
+        stride: usize,
+    ) -> Result<ImageView<'a, T, C>, Error> {
+        check_layout::<C>(data.len(), offset, width, height, stride)?;
+        let end = offset + extent(width, height, stride, C::CHANNELS);
+        Ok(ImageView {
+            meta: Meta::new(width, height),
+            stride,
+            data: &data[offset..end],
+        })
+    }
+}
+
+impl<'a, T: Type, C: Color> GenericImage for ImageView<'a, T, C> {
+    type T = T;
+    type C = C;
+
+    fn meta(&self) -> &Meta<T, C> {
+        &self.meta
+    }
+
+    fn stride(&self) -> usize {
+        self.stride
+    }
+
+    fn data(&self) -> &[T] {
+        self.data
+    }
+}
+
+/// Borrowed, mutable image data
+#[derive(Debug)]
+pub struct ImageViewMut<'a, T: Type, C: Color> {
+    /// Metadata
+    pub meta: Meta<T, C>,
+    stride: usize,
+    data: &'a mut [T],
+}
+
+impl<'a, T: Type, C: Color> ImageViewMut<'a, T, C> {
+    /// Create a mutable view of an existing buffer, `offset` is the index of the first pixel and
+    /// `stride` is the number of elements between the start of two consecutive rows
+    pub fn new(
+        data: &'a mut [T],
+        offset: usize,
+        width: usize,
+        height: usize,
+        stride: usize,
+    ) -> Result<ImageViewMut<'a, T, C>, Error> {
+        check_layout::<C>(data.len(), offset, width, height, stride)?;
+        let end = offset + extent(width, height, stride, C::CHANNELS);
+        Ok(ImageViewMut {
+            meta: Meta::new(width, height),
+            stride,
+            data: &mut data[offset..end],
+        })
+    }
+}
+
+impl<'a, T: Type, C: Color> GenericImage for ImageViewMut<'a, T, C> {
+    type T = T;
+    type C = C;
+
+    fn meta(&self) -> &Meta<T, C> {
+        &self.meta
+    }
+
+    fn stride(&self) -> usize {
+        self.stride
+    }
+
+    fn data(&self) -> &[T] {
+        self.data
+    }
+}
+
+impl<'a, T: Type, C: Color> GenericImageMut for ImageViewMut<'a, T, C> {
+    fn data_mut(&mut self) -> &mut [T] {
+        self.data
+    }
+}
+
+impl<T: Type, C: Color> GenericImage for Image<T, C> {
+    type T = T;
+    type C = C;
+
+    fn meta(&self) -> &Meta<T, C> {
+        &self.meta
+    }
+
+    fn stride(&self) -> usize {
+        self.width_step()
+    }
+
+    fn data(&self) -> &[T] {
+        &self.data
+    }
+}
+
+impl<T: Type, C: Color> GenericImageMut for Image<T, C> {
+    fn data_mut(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_view_region() {
+        let mut image: Image<u8, Gray> = Image::new(4, 3);
+        let mut n = 0;
+        image.each_pixel_mut(|_, px| {
+            px[0] = n;
+            n += 1;
+        });
+
+        let view = image.view(Region::new(1, 1, 2, 2));
+        assert_eq!(view.shape(), (2, 2, 1));
+        assert_eq!(view.get(0, 0), &[5]);
+        assert_eq!(view.row(1), &[9, 10]);
+        assert_eq!(view.meta.data_window(), (1, 1, 2, 2));
+        assert_eq!(view.to_image(), image.crop(Region::new(1, 1, 2, 2)));
+
+        let sub = view.view(Region::new(1, 0, 4, 4));
+        assert_eq!(sub.shape(), (1, 2, 1));
+        assert_eq!(
+            sub.iter().map(|(_, px)| px[0]).collect::<Vec<_>>(),
+            vec![6, 10]
+        );
+
+        let mut view = image.view_mut(Region::new(2, 0, 2, 3));
+        view.for_each(|(x, y), px| px[0] = (x + y * 10) as u8);
+        assert_eq!(image.row(2), &[8, 9, 20, 21]);
+    }
+
+    #[test]
+    fn test_view_slice() {
+        // Two 2x2 RGB images stored side by side with one element of padding per row
+        let data: Vec<f32> = (0..26).map(|x| x as f32 / 100.0).collect();
+        assert!(ImageView::<f32, Rgb>::new(&data, 8, 2, 2, 13).is_err());
+
+        let view = ImageView::<f32, Rgb>::new(&data, 6, 2, 2, 13).unwrap();
+        assert_eq!(view.get(1, 1), &[0.22, 0.23, 0.24]);
+
+        let mut out = vec![0u16; 26];
+        let mut dest = ImageViewMut::<u16, Rgb>::new(&mut out, 0, 2, 2, 13).unwrap();
+        filter::Invert.eval(&mut dest, &[&view]);
+        assert!((dest.get_f(1, 1, 0) - 0.78).abs() < 1e-4);
+        assert!(out[6..13].iter().all(|x| *x == 0));
+        assert!(out[13..19].iter().all(|x| *x > 0));
+    }
+}